@@ -0,0 +1,203 @@
+use crate::{AsyncStorageInspect, AsyncStorageMutate, Mappable, StorageInspect, StorageMutate};
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use alloc::{sync::Arc, task::Wake};
+#[cfg(not(feature = "std"))]
+use core::hint;
+use core::{
+    future::Future,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+#[cfg(feature = "std")]
+use std::thread;
+
+/// Run the `future` to completion on the current thread and return its output.
+///
+/// With the `std` feature, the thread is parked while the future is pending and unparked when
+/// the future is woken, so the future may wait for other threads. Without it, the executor polls
+/// the future in a loop without sleeping between polls and the waker does nothing, so it suits
+/// only futures that never return [`Poll::Pending`] or are ready again on the next poll, such as
+/// the futures of the [`AsyncStorage`]. Futures that need the reactor of a specific runtime should
+/// be run by the executor of the application.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{block_on, AsyncStorage, AsyncStorageMutate, Mappable, MemoryStorage};
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = AsyncStorage::new(MemoryStorage::new());
+/// let previous = block_on(async {
+///     AsyncStorageMutate::<Balances>::insert(&mut storage, &1, &10).await?;
+///     AsyncStorageMutate::<Balances>::insert(&mut storage, &1, &20).await
+/// });
+/// assert_eq!(previous, Ok(Some(10)));
+/// ```
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    #[cfg(feature = "std")]
+    let waker = &Waker::from(Arc::new(ThreadWaker(thread::current())));
+    #[cfg(not(feature = "std"))]
+    let waker = Waker::noop();
+    let mut context = Context::from_waker(waker);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            #[cfg(feature = "std")]
+            Poll::Pending => thread::park(),
+            #[cfg(not(feature = "std"))]
+            Poll::Pending => hint::spin_loop(),
+        }
+    }
+}
+
+/// The waker that unparks the thread blocked in the [`block_on`].
+#[cfg(feature = "std")]
+struct ThreadWaker(thread::Thread);
+
+#[cfg(feature = "std")]
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// The adapter that serves the synchronous storage through the [`AsyncStorageInspect`] and the
+/// [`AsyncStorageMutate`]. Every future completes on the first poll.
+#[derive(Debug, Default, Clone)]
+pub struct AsyncStorage<S> {
+    storage: S,
+}
+
+impl<S> AsyncStorage<S> {
+    /// Wrap the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Return the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Unwrap the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S: StorageInspect<Type>, Type: Mappable> AsyncStorageInspect<Type> for AsyncStorage<S> {
+    type Error = S::Error;
+
+    async fn get<'a>(
+        &'a self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'a, Type::GetValue>>, Self::Error>
+    where
+        Type::GetValue: 'a,
+    {
+        self.storage.get(key)
+    }
+
+    async fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        self.storage.contains_key(key)
+    }
+}
+
+impl<S: StorageMutate<Type>, Type: Mappable> AsyncStorageMutate<Type> for AsyncStorage<S> {
+    async fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        self.storage.insert(key, value)
+    }
+
+    async fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        self.storage.remove(key)
+    }
+}
+
+/// The adapter that serves the asynchronous storage through the [`StorageInspect`] and the
+/// [`StorageMutate`], blocking on every future with the [`block_on`].
+///
+/// Without the `std` feature, the [`block_on`] spins until the future is ready, so the adapter
+/// only works with storages whose futures never return [`Poll::Pending`] or are ready again on
+/// the next poll. Storages whose futures wait for other threads need the `std` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{AsyncStorage, BlockingStorage, Mappable, MemoryStorage, StorageAsMut};
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = BlockingStorage::new(AsyncStorage::new(MemoryStorage::new()));
+/// storage.storage::<Balances>().insert(&1, &10).unwrap();
+/// assert_eq!(storage.storage::<Balances>().remove(&1), Ok(Some(10)));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct BlockingStorage<S> {
+    storage: S,
+}
+
+impl<S> BlockingStorage<S> {
+    /// Wrap the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Return the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Unwrap the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S: AsyncStorageInspect<Type>, Type: Mappable> StorageInspect<Type> for BlockingStorage<S> {
+    type Error = S::Error;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        block_on(self.storage.get(key))
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        block_on(self.storage.contains_key(key))
+    }
+}
+
+impl<S: AsyncStorageMutate<Type>, Type: Mappable> StorageMutate<Type> for BlockingStorage<S> {
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        block_on(self.storage.insert(key, value))
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        block_on(self.storage.remove(key))
+    }
+}
@@ -0,0 +1,433 @@
+use crate::{
+    BoxedIter, IterDirection, KVItem, Mappable, MerkleMultiproofStorage, MerkleProofStorage,
+    MerkleRoot, MerkleRootStorage, MerkleSumRootStorage, StorageBatchMutate, StorageInspect,
+    StorageIterate, StorageMutate, StorageWrite,
+};
+use alloc::{borrow::Cow, boxed::Box, collections::BTreeMap};
+use core::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    mem,
+    ops::Bound,
+};
+
+/// The bound of the cache of every table of the [`CachedStorage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheCapacity {
+    /// The maximum number of cached values.
+    Entries(usize),
+    /// The maximum total weight of cached keys and values, as measured by the weigher of the
+    /// table, in bytes.
+    Bytes(usize),
+}
+
+/// The number of reads of one table served from the cache and from the underlying storage.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheStats {
+    /// The number of reads served from the cache.
+    pub hits: u64,
+    /// The number of reads served from the underlying storage.
+    pub misses: u64,
+}
+
+/// The function that returns the weight of the cached entry of the `Type` in bytes.
+pub type Weigher<Type> = fn(&<Type as Mappable>::Key, &<Type as Mappable>::GetValue) -> usize;
+
+/// The wrapper around the storage that keeps the recently read values of every table in memory,
+/// evicting the least recently used values when the cache of the table exceeds the
+/// [`CacheCapacity`].
+///
+/// Values are cached on reads and invalidated on writes, so the cache never serves the value
+/// replaced through the wrapper. The underlying storage shouldn't be modified while wrapped. The
+/// `contains_key` is served from the cache if the value is cached but doesn't cache it, and
+/// iterations go to the storage without touching the cache. By default the weight of an entry is
+/// the size of the key and the value types, tables with values owning heap memory should set their
+/// [`Weigher`] with the [`set_weigher`](Self::set_weigher).
+///
+/// The merkle methods go to the storage too, but the merkle trees may write to any table while
+/// computing roots and proofs, so every `root`, `root_and_sum`, `prove` and `prove_many` empties
+/// the caches of all tables. The reads after them miss until the caches are filled again, so
+/// callers that read the same values around the merkle methods pay for the refill every time.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     CacheCapacity, CacheStats, CachedStorage, Mappable, MemoryStorage, StorageAsMut,
+/// };
+///
+/// pub struct ContractsBytecode;
+///
+/// impl Mappable for ContractsBytecode {
+///     type Key = [u8; 32];
+///     type SetValue = [u8];
+///     type GetValue = Vec<u8>;
+/// }
+///
+/// let mut storage = MemoryStorage::new();
+/// storage.storage::<ContractsBytecode>().insert(&[1; 32], &[0; 600]).unwrap();
+/// storage.storage::<ContractsBytecode>().insert(&[2; 32], &[0; 600]).unwrap();
+///
+/// let mut cached = CachedStorage::new(&mut storage, CacheCapacity::Bytes(1024));
+/// cached.set_weigher::<ContractsBytecode>(|_, bytecode| 32 + bytecode.len());
+/// for _ in 0..3 {
+///     cached.storage::<ContractsBytecode>().get(&[1; 32]).unwrap();
+/// }
+/// // Only one bytecode fits into the cache, the other one evicts it.
+/// cached.storage::<ContractsBytecode>().get(&[2; 32]).unwrap();
+/// cached.storage::<ContractsBytecode>().get(&[1; 32]).unwrap();
+///
+/// assert_eq!(cached.stats::<ContractsBytecode>(), CacheStats { hits: 2, misses: 3 });
+/// ```
+#[derive(Debug)]
+pub struct CachedStorage<S> {
+    storage: S,
+    capacity: CacheCapacity,
+    caches: RefCell<BTreeMap<TypeId, ErasedCache>>,
+}
+
+/// The type-erased [`TableCache`], tables are distinguished by the type of the `Mappable`.
+#[derive(Debug)]
+struct ErasedCache {
+    cache: Box<dyn Any>,
+    clear: fn(&mut dyn Any),
+}
+
+impl<S> CachedStorage<S> {
+    /// Wrap the `storage` with the empty cache of the `capacity` for every table.
+    pub fn new(storage: S, capacity: CacheCapacity) -> Self {
+        Self {
+            storage,
+            capacity,
+            caches: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Return the capacity of the cache of every table.
+    pub fn capacity(&self) -> CacheCapacity {
+        self.capacity
+    }
+
+    /// Return the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Unwrap the underlying storage, dropping the cache.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Set the `weigher` of the entries of the `Type`, used by the [`CacheCapacity::Bytes`].
+    /// Cached values of the `Type` are dropped.
+    pub fn set_weigher<Type>(&mut self, weigher: Weigher<Type>)
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::GetValue: 'static,
+    {
+        self.with_cache::<Type, _>(|cache| {
+            cache.clear();
+            cache.weigher = weigher;
+        })
+    }
+
+    /// Return the hits and the misses of the reads of the `Type`.
+    pub fn stats<Type>(&self) -> CacheStats
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::GetValue: 'static,
+    {
+        self.with_cache::<Type, _>(|cache| cache.stats)
+    }
+
+    /// Drop the cached values of all tables, keeping the stats and the weighers.
+    pub fn clear(&mut self) {
+        for erased in self.caches.get_mut().values_mut() {
+            (erased.clear)(erased.cache.as_mut());
+        }
+    }
+
+    fn with_cache<Type, R>(&self, f: impl FnOnce(&mut TableCache<Type>) -> R) -> R
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::GetValue: 'static,
+    {
+        let mut caches = self.caches.borrow_mut();
+        let cache = caches
+            .entry(TypeId::of::<Type>())
+            .or_insert_with(|| ErasedCache {
+                cache: Box::new(TableCache::<Type>::new()),
+                clear: clear::<Type>,
+            })
+            .cache
+            .downcast_mut()
+            .expect("The cache is always created with the type of the `Mappable`");
+        f(cache)
+    }
+}
+
+fn clear<Type>(cache: &mut dyn Any)
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+{
+    cache
+        .downcast_mut::<TableCache<Type>>()
+        .expect("The clear function is always registered with the type of the cache")
+        .clear();
+}
+
+/// The LRU cache of one table. Every access takes the next tick, and the entries are evicted in
+/// the order of their last ticks.
+struct TableCache<Type: Mappable> {
+    /// The cached values with the ticks of their last accesses and their weights.
+    entries: BTreeMap<Type::Key, (Type::GetValue, u64, usize)>,
+    /// The keys of the cached values by the ticks of their last accesses.
+    recency: BTreeMap<u64, Type::Key>,
+    tick: u64,
+    weight: usize,
+    weigher: Weigher<Type>,
+    stats: CacheStats,
+}
+
+impl<Type> TableCache<Type>
+where
+    Type: Mappable,
+    Type::Key: Ord + Clone,
+{
+    fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            weight: 0,
+            weigher: |_, _| mem::size_of::<Type::Key>() + mem::size_of::<Type::GetValue>(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Return the cached value of the `key` marking it as the most recently used one.
+    fn get(&mut self, key: &Type::Key) -> Option<&Type::GetValue> {
+        let (value, tick, _) = self.entries.get_mut(key)?;
+        self.recency.remove(tick);
+        self.tick += 1;
+        *tick = self.tick;
+        self.recency.insert(self.tick, key.clone());
+        Some(value)
+    }
+
+    /// Cache the `value` of the `key` evicting the least recently used values beyond the
+    /// `capacity`. The value heavier than the whole capacity isn't cached.
+    fn insert(&mut self, key: &Type::Key, value: &Type::GetValue, capacity: CacheCapacity) {
+        self.remove(key);
+        let weight = (self.weigher)(key, value);
+        let fits = |entries: usize, total: usize| match capacity {
+            CacheCapacity::Entries(limit) => entries <= limit,
+            CacheCapacity::Bytes(limit) => total <= limit,
+        };
+        if !fits(1, weight) {
+            return;
+        }
+        while !fits(self.entries.len() + 1, self.weight + weight) {
+            let Some((_, evicted)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some((_, _, evicted)) = self.entries.remove(&evicted) {
+                self.weight -= evicted;
+            }
+        }
+        self.tick += 1;
+        self.weight += weight;
+        self.entries
+            .insert(key.clone(), (value.clone(), self.tick, weight));
+        self.recency.insert(self.tick, key.clone());
+    }
+
+    fn remove(&mut self, key: &Type::Key) {
+        if let Some((_, tick, weight)) = self.entries.remove(key) {
+            self.recency.remove(&tick);
+            self.weight -= weight;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.weight = 0;
+    }
+}
+
+impl<Type, S> StorageInspect<Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: StorageInspect<Type>,
+{
+    type Error = S::Error;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        let cached = self.with_cache::<Type, _>(|cache| {
+            let cached = cache.get(key).cloned();
+            match cached {
+                Some(_) => cache.stats.hits += 1,
+                None => cache.stats.misses += 1,
+            }
+            cached
+        });
+        if let Some(value) = cached {
+            return Ok(Some(Cow::Owned(value)));
+        }
+
+        let value = self.storage.get(key)?;
+        if let Some(value) = &value {
+            self.with_cache::<Type, _>(|cache| cache.insert(key, value, self.capacity));
+        }
+        Ok(value)
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        if self.with_cache::<Type, _>(|cache| cache.entries.contains_key(key)) {
+            return Ok(true);
+        }
+        self.storage.contains_key(key)
+    }
+}
+
+impl<Type, S> StorageMutate<Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: StorageMutate<Type>,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        self.with_cache::<Type, _>(|cache| cache.remove(key));
+        self.storage.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        self.with_cache::<Type, _>(|cache| cache.remove(key));
+        self.storage.remove(key)
+    }
+}
+
+impl<Type, S> StorageWrite<Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: StorageMutate<Type>,
+{
+}
+
+impl<Type, S> StorageBatchMutate<Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: StorageMutate<Type>,
+{
+}
+
+impl<Type, S> StorageIterate<Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: StorageIterate<Type>,
+{
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        self.storage.iter_range(start, end, direction)
+    }
+
+    fn iter_all(&self, direction: IterDirection) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        self.storage.iter_all(direction)
+    }
+
+    fn iter_prefix<'a>(
+        &'a self,
+        prefix: &[u8],
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, Self::Error>>
+    where
+        Type::Key: AsRef<[u8]> + 'a,
+        Type::GetValue: 'a,
+        Self::Error: 'a,
+    {
+        self.storage.iter_prefix(prefix, direction)
+    }
+}
+
+impl<Key, Type, S> MerkleRootStorage<Key, Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: MerkleRootStorage<Key, Type>,
+{
+    fn root(&mut self, key: &Key) -> Result<MerkleRoot, Self::Error> {
+        self.clear();
+        self.storage.root(key)
+    }
+}
+
+impl<Key, Type, S> MerkleSumRootStorage<Key, Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: MerkleSumRootStorage<Key, Type>,
+{
+    fn root_and_sum(&mut self, key: &Key) -> Result<(MerkleRoot, u64), Self::Error> {
+        self.clear();
+        self.storage.root_and_sum(key)
+    }
+}
+
+impl<Key, Type, S> MerkleProofStorage<Key, Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: MerkleProofStorage<Key, Type>,
+{
+    type Proof = S::Proof;
+
+    fn prove(&mut self, key: &Key, storage_key: &Type::Key) -> Result<Self::Proof, Self::Error> {
+        self.clear();
+        self.storage.prove(key, storage_key)
+    }
+}
+
+impl<Key, Type, S> MerkleMultiproofStorage<Key, Type> for CachedStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: MerkleMultiproofStorage<Key, Type>,
+{
+    type Multiproof = S::Multiproof;
+
+    fn prove_many(
+        &mut self,
+        key: &Key,
+        storage_keys: &[Type::Key],
+    ) -> Result<Self::Multiproof, Self::Error> {
+        self.clear();
+        self.storage.prove_many(key, storage_keys)
+    }
+}
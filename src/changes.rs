@@ -0,0 +1,451 @@
+use crate::{
+    borrow,
+    codec::{CompactDecode, CompactEncode, Decode, DecodeError, Encode},
+    iter,
+    kv_store::{IterableKeyValueStore, KeyValueItem, KeyValueStore},
+    merkle::MerkleHasher,
+    BoxedIter, IterDirection, Mappable, MerkleRoot, StorageMutate, Table, TableWithCodec,
+};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+    vec::Vec,
+};
+use core::ops::Bound;
+
+/// The changes of one column: the new value of every changed key, `None` if it was removed.
+type ColumnChanges = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// The set of changes to the storage: the new value of every changed key, or the removal of the
+/// key, per column. The changes are produced by the [`KeyValueOverlay`] and applied to any
+/// [`KeyValueStore`] with the [`KeyValueStore::apply`] or to the table of any [`StorageMutate`]
+/// with the [`apply_table`](Self::apply_table). The reversible application also returns the
+/// inverse changes that restore the previous state, kept to unwind the last blocks.
+///
+/// The application is atomic only as far as the storage allows: the [`KeyValueStore::apply`] is
+/// atomic for stores with atomic batches of writes, and the reversible application and the
+/// [`apply_table`](Self::apply_table) undo the written part of the changes if a write fails.
+///
+/// Columns and keys are ordered, so the changes have the single binary format, used to ship the
+/// diffs of blocks between nodes, to store them for rollbacks and to [`hash`](Self::hash) them.
+/// The format starts with the [`VERSION`](Self::VERSION) byte followed by the
+/// [`Compact`](crate::codec::Compact) encoding of the number of columns and every column: the
+/// column id, the number of changes and every change as the key and the optional value, both as
+/// byte strings. Columns and keys are in the ascending order, without duplicates.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::Compact, Changes, KeyValueStore, Mappable, MemoryKeyValueStore, StorageAsMut,
+///     StructuredStorage, Table, TableWithCodec,
+/// };
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// impl TableWithCodec for Balances {
+///     type KeyCodec = Compact;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for Balances {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "Balances";
+/// }
+///
+/// let mut changes = Changes::new();
+/// changes.insert::<Balances>(&1, &100);
+/// changes.insert::<Balances>(&2, &200);
+/// changes.remove::<Balances>(&2);
+///
+/// // The changes are shipped to another node in the binary format.
+/// let changes = Changes::decode(&changes.encode()).unwrap();
+///
+/// let mut storage = StructuredStorage::new(MemoryKeyValueStore::new());
+/// storage.storage::<Balances>().insert(&2, &20).unwrap();
+/// let inverse = changes.apply_reversibly(storage.inner_mut()).unwrap();
+/// assert_eq!(storage.storage::<Balances>().get(&1).unwrap().unwrap().into_owned(), 100);
+/// assert!(!storage.storage::<Balances>().contains_key(&2).unwrap());
+///
+/// // The inverse changes unwind the block.
+/// storage.inner_mut().apply(&inverse).unwrap();
+/// assert!(!storage.storage::<Balances>().contains_key(&1).unwrap());
+/// assert_eq!(storage.storage::<Balances>().get(&2).unwrap().unwrap().into_owned(), 20);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Changes {
+    columns: BTreeMap<u32, ColumnChanges>,
+}
+
+impl Changes {
+    /// The version of the binary format.
+    pub const VERSION: u8 = 1;
+
+    /// Create the empty set of changes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return `true` if there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Return the number of changed keys in all columns.
+    pub fn len(&self) -> usize {
+        self.columns.values().map(BTreeMap::len).sum()
+    }
+
+    /// Record storing the `value` under the `key` in the `column`.
+    pub fn put(&mut self, column: u32, key: &[u8], value: &[u8]) {
+        self.change(column, key, Some(value.to_vec()));
+    }
+
+    /// Record removing the `key` from the `column`.
+    pub fn delete(&mut self, column: u32, key: &[u8]) {
+        self.change(column, key, None);
+    }
+
+    /// Record storing the `value` under the `key` in the table.
+    pub fn insert<Type: Table + TableWithCodec>(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) {
+        let key = Type::KeyCodec::encode(key);
+        let value = Type::ValueCodec::encode(value);
+        self.put(Type::COLUMN, &key, &value);
+    }
+
+    /// Record removing the `key` from the table.
+    pub fn remove<Type: Table + TableWithCodec>(&mut self, key: &Type::Key) {
+        self.delete(Type::COLUMN, &Type::KeyCodec::encode(key));
+    }
+
+    /// Return the change of the `key` in the `column`: `Some(None)` if the key is removed and
+    /// `None` if the key isn't changed.
+    pub fn get(&self, column: u32, key: &[u8]) -> Option<Option<&[u8]>> {
+        self.columns
+            .get(&column)?
+            .get(key)
+            .map(|value| value.as_deref())
+    }
+
+    /// Iterate over the changes in the ascending order of columns and keys.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &[u8], Option<&[u8]>)> + '_ {
+        self.columns.iter().flat_map(|(column, changes)| {
+            changes
+                .iter()
+                .map(|(key, value)| (*column, key.as_slice(), value.as_deref()))
+        })
+    }
+
+    /// Add the `other` changes on top of these, overriding the changes of the same keys.
+    pub fn extend(&mut self, other: Changes) {
+        for (column, changes) in other.columns {
+            self.columns.entry(column).or_default().extend(changes);
+        }
+    }
+
+    /// Apply the changes of the table to the `storage` and return the inverse changes of the
+    /// table, built from the values of the changed keys before the writes. Applying the inverse
+    /// changes restores the previous state of the table.
+    ///
+    /// All changes of the table are decoded and the previous values are read before the first
+    /// write, so the invalid encoding leaves the storage intact. If a write fails, the written
+    /// part of the changes is undone before the error is returned, so the storage keeps its
+    /// previous state unless the undoing fails too.
+    pub fn apply_table<Type, S>(&self, storage: &mut S) -> Result<Changes, S::Error>
+    where
+        Type: Table + TableWithCodec,
+        Type::SetValue: ToOwned<Owned = Type::GetValue>,
+        S: StorageMutate<Type> + ?Sized,
+        S::Error: From<DecodeError>,
+    {
+        let mut inverse = Changes::new();
+        let Some(changes) = self.columns.get(&Type::COLUMN) else {
+            return Ok(inverse);
+        };
+        let mut decoded = Vec::with_capacity(changes.len());
+        for (encoded_key, value) in changes {
+            let key: Type::Key = Type::KeyCodec::decode(encoded_key)?;
+            let value: Option<Type::GetValue> =
+                value.as_deref().map(Type::ValueCodec::decode).transpose()?;
+            let previous = storage.get(&key)?.map(Cow::into_owned);
+            let encoded = previous
+                .as_ref()
+                .map(|previous| Type::ValueCodec::encode(borrow::<Type::SetValue>(previous)));
+            inverse.change(Type::COLUMN, encoded_key, encoded.map(Cow::into_owned));
+            decoded.push((key, value, previous));
+        }
+
+        for (written, (key, value, _)) in decoded.iter().enumerate() {
+            if let Err(error) = write::<Type, S>(storage, key, value) {
+                // The writes of the table can't be batched, so the written ones are undone here.
+                for (key, _, previous) in decoded[..written].iter().rev() {
+                    let _ = write::<Type, S>(storage, key, previous);
+                }
+                return Err(error);
+            }
+        }
+        Ok(inverse)
+    }
+
+    /// Apply the changes to the `store` and return the inverse changes, built from the values of
+    /// the changed keys before the writes. Applying the inverse changes restores the previous
+    /// state of the store, which is used to unwind blocks on reorganisations.
+    ///
+    /// The previous values are read before the changes are written with the
+    /// [`KeyValueStore::apply`]. If it fails, the inverse changes are applied to undo the written
+    /// part before the error is returned, so the store keeps its previous state unless the
+    /// undoing fails too.
+    pub fn apply_reversibly<S: KeyValueStore + ?Sized>(
+        &self,
+        store: &mut S,
+    ) -> Result<Changes, S::Error> {
+        let mut inverse = Changes::new();
+        for (column, key, _) in self.iter() {
+            inverse.change(column, key, store.get(column, key)?);
+        }
+        if let Err(error) = store.apply(self) {
+            let _ = store.apply(&inverse);
+            return Err(error);
+        }
+        Ok(inverse)
+    }
+
+    /// Return the hash of the binary format of the changes.
+    pub fn hash<H: MerkleHasher>(&self) -> MerkleRoot {
+        H::hash(&self.encode())
+    }
+
+    /// Return the encoding of the changes in the binary format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = alloc::vec![Self::VERSION];
+        self.columns.len().encode_compact(&mut buf);
+        for (column, changes) in &self.columns {
+            column.encode_compact(&mut buf);
+            changes.len().encode_compact(&mut buf);
+            for (key, value) in changes {
+                key.encode_compact(&mut buf);
+                value.encode_compact(&mut buf);
+            }
+        }
+        buf
+    }
+
+    /// Decode the changes from the binary format. Columns and keys out of the ascending order are
+    /// rejected, so every set of changes has the single encoding.
+    pub fn decode(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        let bytes = &mut bytes;
+        let version = u8::decode_compact(bytes)?;
+        if version != Self::VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let mut columns = BTreeMap::new();
+        for _ in 0..usize::decode_compact(bytes)? {
+            let column = u32::decode_compact(bytes)?;
+            if columns
+                .last_key_value()
+                .is_some_and(|(last, _)| *last >= column)
+            {
+                return Err(DecodeError::InvalidValue);
+            }
+            let mut changes = ColumnChanges::new();
+            for _ in 0..usize::decode_compact(bytes)? {
+                let key = Vec::<u8>::decode_compact(bytes)?;
+                if changes
+                    .last_key_value()
+                    .is_some_and(|(last, _)| *last >= key)
+                {
+                    return Err(DecodeError::InvalidValue);
+                }
+                changes.insert(key, CompactDecode::decode_compact(bytes)?);
+            }
+            if changes.is_empty() {
+                return Err(DecodeError::InvalidValue);
+            }
+            columns.insert(column, changes);
+        }
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(Self { columns })
+    }
+
+    fn change(&mut self, column: u32, key: &[u8], value: Option<Vec<u8>>) {
+        self.columns
+            .entry(column)
+            .or_default()
+            .insert(key.to_vec(), value);
+    }
+}
+
+/// Store the `value` under the `key` of the table, or remove the key if there is no value.
+fn write<Type, S>(
+    storage: &mut S,
+    key: &Type::Key,
+    value: &Option<Type::GetValue>,
+) -> Result<(), S::Error>
+where
+    Type: Mappable,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    S: StorageMutate<Type> + ?Sized,
+{
+    match value {
+        Some(value) => storage.insert(key, borrow::<Type::SetValue>(value))?,
+        None => storage.remove(key)?,
+    };
+    Ok(())
+}
+
+/// The [`KeyValueStore`] that buffers the writes on top of the underlying store as the
+/// [`Changes`]. Reads and iterations see the buffered writes; the underlying store isn't modified
+/// until the [`commit`](Self::commit).
+///
+/// Unlike the [`StorageTransaction`](crate::StorageTransaction), which keeps the typed values of
+/// every table, the overlay works with the encoded bytes, so the changes of all tables served by
+/// the [`StructuredStorage`](crate::StructuredStorage) on top of it are produced as one set.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::Compact, KeyValueOverlay, Mappable, MemoryKeyValueStore, StorageAsMut,
+///     StructuredStorage, Table, TableWithCodec,
+/// };
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// impl TableWithCodec for Balances {
+///     type KeyCodec = Compact;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for Balances {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "Balances";
+/// }
+///
+/// let mut storage = MemoryKeyValueStore::new();
+/// let mut block = StructuredStorage::new(KeyValueOverlay::new(&mut storage));
+/// block.storage::<Balances>().insert(&1, &100).unwrap();
+/// block.storage::<Balances>().insert(&2, &200).unwrap();
+///
+/// let (_, changes) = block.into_inner().commit().unwrap();
+/// assert_eq!(changes.len(), 2);
+///
+/// let mut storage = StructuredStorage::new(storage);
+/// assert_eq!(storage.storage::<Balances>().get(&2).unwrap().unwrap().into_owned(), 200);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct KeyValueOverlay<S> {
+    store: S,
+    changes: Changes,
+}
+
+impl<S> KeyValueOverlay<S> {
+    /// Start buffering the writes on top of the `store`.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            changes: Changes::new(),
+        }
+    }
+
+    /// Return the underlying store. Its state doesn't include the buffered changes.
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Return the buffered changes.
+    pub fn changes(&self) -> &Changes {
+        &self.changes
+    }
+
+    /// Discard the buffered changes and return the underlying store.
+    pub fn rollback(self) -> S {
+        self.store
+    }
+
+    /// Return the underlying store and the buffered changes without applying them.
+    pub fn into_parts(self) -> (S, Changes) {
+        (self.store, self.changes)
+    }
+}
+
+impl<S: KeyValueStore> KeyValueOverlay<S> {
+    /// Apply the buffered changes to the underlying store and return it with the changes.
+    ///
+    /// The changes are applied by the [`KeyValueStore::apply`] of the underlying store.
+    pub fn commit(mut self) -> Result<(S, Changes), S::Error> {
+        self.store.apply(&self.changes)?;
+        Ok((self.store, self.changes))
+    }
+}
+
+impl<S: KeyValueStore> KeyValueStore for KeyValueOverlay<S> {
+    type Error = S::Error;
+
+    fn get(&self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        match self.changes.get(column, key) {
+            Some(value) => Ok(value.map(<[u8]>::to_vec)),
+            None => self.store.get(column, key),
+        }
+    }
+
+    fn exists(&self, column: u32, key: &[u8]) -> Result<bool, Self::Error> {
+        match self.changes.get(column, key) {
+            Some(value) => Ok(value.is_some()),
+            None => self.store.exists(column, key),
+        }
+    }
+
+    fn put(&mut self, column: u32, key: &[u8], value: &[u8]) -> Result<(), Self::Error> {
+        self.changes.put(column, key, value);
+        Ok(())
+    }
+
+    fn delete(&mut self, column: u32, key: &[u8]) -> Result<(), Self::Error> {
+        self.changes.delete(column, key);
+        Ok(())
+    }
+}
+
+impl<S: IterableKeyValueStore> IterableKeyValueStore for KeyValueOverlay<S> {
+    fn iter_column(
+        &self,
+        column: u32,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KeyValueItem<Self::Error>> {
+        let changes = self
+            .changes
+            .columns
+            .get(&column)
+            .and_then(|changes| iter::btree_range(changes, start, end))
+            .into_iter()
+            .flatten();
+        let changes = match direction {
+            IterDirection::Forward => changes.collect(),
+            IterDirection::Reverse => changes.rev().collect(),
+        };
+        let inner = self.store.iter_column(column, start, end, direction);
+        Box::new(iter::MergedIter::new(inner, changes, direction))
+    }
+}
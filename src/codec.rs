@@ -0,0 +1,433 @@
+//! Conversion of keys and values of [`Mappable`] tables into bytes and back.
+//!
+//! The [`TableWithCodec`] extension of the [`Mappable`] declares which codec is used for the key
+//! and for the value of the table, which allows byte-oriented backends to serve any table.
+
+use crate::Mappable;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
+
+/// The error returned when bytes don't represent a value of the requested type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodeError {
+    /// The input ended before the value was decoded.
+    UnexpectedEnd,
+    /// The input contains bytes after the decoded value.
+    TrailingBytes,
+    /// The length of the input doesn't match the size of the value.
+    InvalidLength,
+    /// The input doesn't represent a valid value.
+    InvalidValue,
+    /// The input is in the version of the format that isn't supported.
+    UnsupportedVersion(u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of the input"),
+            Self::TrailingBytes => write!(f, "trailing bytes after the value"),
+            Self::InvalidLength => write!(f, "the length doesn't match the size of the value"),
+            Self::InvalidValue => write!(f, "the input doesn't represent a valid value"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "the version {version} of the format isn't supported")
+            }
+        }
+    }
+}
+
+/// The codec that encodes values of the type `T` into bytes.
+pub trait Encode<T: ?Sized> {
+    /// Encode the `value` into bytes. Codecs that don't transform the value may borrow it.
+    fn encode(value: &T) -> Cow<'_, [u8]>;
+}
+
+/// The codec that decodes values of the type `T` from bytes.
+pub trait Decode<T> {
+    /// Decode the value from the `bytes`. All bytes should be consumed.
+    fn decode(bytes: &[u8]) -> Result<T, DecodeError>;
+}
+
+/// The [`Mappable`] with the codecs for its key and its value.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::{Array, BigEndian, Decode, Encode, Raw},
+///     Mappable, TableWithCodec,
+/// };
+///
+/// pub struct Contracts;
+///
+/// impl Mappable for Contracts {
+///     type Key = [u8; 32];
+///     type SetValue = [u8];
+///     type GetValue = Vec<u8>;
+/// }
+///
+/// impl TableWithCodec for Contracts {
+///     type KeyCodec = Array;
+///     type ValueCodec = Raw;
+/// }
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// impl TableWithCodec for Balances {
+///     type KeyCodec = BigEndian;
+///     type ValueCodec = BigEndian;
+/// }
+///
+/// let key = <Balances as TableWithCodec>::KeyCodec::encode(&0x0102u128);
+/// assert_eq!(key.len(), 16);
+/// assert_eq!(<Balances as TableWithCodec>::KeyCodec::decode(&key), Ok(0x0102u128));
+///
+/// let value = <Contracts as TableWithCodec>::ValueCodec::encode(&[1, 2, 3][..]);
+/// assert_eq!(value.as_ref(), &[1, 2, 3]);
+/// ```
+pub trait TableWithCodec: Mappable {
+    /// The codec of the `Key`.
+    type KeyCodec: Encode<Self::Key> + Decode<Self::Key>;
+    /// The codec of the value. It encodes the `SetValue` and decodes the `GetValue`.
+    type ValueCodec: Encode<Self::SetValue> + Decode<Self::GetValue>;
+}
+
+/// The codec that uses bytes of the value as is. It decodes into any type that can be created from
+/// a slice of bytes, like `Vec<u8>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Raw;
+
+impl<T: AsRef<[u8]> + ?Sized> Encode<T> for Raw {
+    fn encode(value: &T) -> Cow<'_, [u8]> {
+        Cow::Borrowed(value.as_ref())
+    }
+}
+
+impl<T: for<'a> From<&'a [u8]>> Decode<T> for Raw {
+    fn decode(bytes: &[u8]) -> Result<T, DecodeError> {
+        Ok(T::from(bytes))
+    }
+}
+
+/// The codec for fixed-size byte arrays. Decoding fails if the length of the input doesn't match
+/// the size of the array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Array;
+
+impl<const N: usize> Encode<[u8; N]> for Array {
+    fn encode(value: &[u8; N]) -> Cow<'_, [u8]> {
+        Cow::Borrowed(value.as_slice())
+    }
+}
+
+impl<const N: usize> Decode<[u8; N]> for Array {
+    fn decode(bytes: &[u8]) -> Result<[u8; N], DecodeError> {
+        bytes.try_into().map_err(|_| DecodeError::InvalidLength)
+    }
+}
+
+/// The codec for unsigned integers in the big-endian byte order. The byte order of encoded values
+/// matches the numeric order, so it is suitable for keys of ordered tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BigEndian;
+
+macro_rules! impl_big_endian {
+    ($($ty:ty),*) => {$(
+        impl Encode<$ty> for BigEndian {
+            fn encode(value: &$ty) -> Cow<'_, [u8]> {
+                Cow::Owned(value.to_be_bytes().to_vec())
+            }
+        }
+
+        impl Decode<$ty> for BigEndian {
+            fn decode(bytes: &[u8]) -> Result<$ty, DecodeError> {
+                bytes
+                    .try_into()
+                    .map(<$ty>::from_be_bytes)
+                    .map_err(|_| DecodeError::InvalidLength)
+            }
+        }
+    )*};
+}
+
+impl_big_endian!(u8, u16, u32, u64, u128);
+
+/// The compact variable-length codec in the style of postcard: integers are LEB128 varints
+/// (zig-zag for signed ones), sequences are prefixed with the varint length, and tuples and arrays
+/// are concatenations of their elements. The encoding of a value is canonical, so it can be
+/// hashed.
+///
+/// Types are supported through the [`CompactEncode`] and [`CompactDecode`] traits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compact;
+
+impl<T: CompactEncode + ?Sized> Encode<T> for Compact {
+    fn encode(value: &T) -> Cow<'_, [u8]> {
+        let mut buf = Vec::new();
+        value.encode_compact(&mut buf);
+        Cow::Owned(buf)
+    }
+}
+
+impl<T: CompactDecode> Decode<T> for Compact {
+    fn decode(mut bytes: &[u8]) -> Result<T, DecodeError> {
+        let value = T::decode_compact(&mut bytes)?;
+        if bytes.is_empty() {
+            Ok(value)
+        } else {
+            Err(DecodeError::TrailingBytes)
+        }
+    }
+}
+
+/// The type that can be encoded with the [`Compact`] codec.
+pub trait CompactEncode {
+    /// Append the encoding of `self` to the `buf`.
+    fn encode_compact(&self, buf: &mut Vec<u8>);
+}
+
+/// The type that can be decoded with the [`Compact`] codec.
+pub trait CompactDecode: Sized {
+    /// Decode the value from the beginning of the `bytes` and advance them past it.
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError>;
+}
+
+/// Take the first `len` bytes of the input.
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], DecodeError> {
+    if bytes.len() < len {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn encode_varint(mut value: u128, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Decode the varint that fits into `bits`. Non-canonical encodings are rejected.
+fn decode_varint(bytes: &mut &[u8], bits: u32) -> Result<u128, DecodeError> {
+    let mut value = 0u128;
+    let mut shift = 0;
+    loop {
+        let byte = take(bytes, 1)?[0];
+        let payload = (byte & 0x7f) as u128;
+        if shift >= bits || payload >> (bits - shift).min(7) != 0 {
+            return Err(DecodeError::InvalidValue);
+        }
+        value |= payload << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if shift > 7 && payload == 0 {
+                return Err(DecodeError::InvalidValue);
+            }
+            return Ok(value);
+        }
+    }
+}
+
+macro_rules! impl_compact_unsigned {
+    ($($ty:ty),*) => {$(
+        impl CompactEncode for $ty {
+            fn encode_compact(&self, buf: &mut Vec<u8>) {
+                encode_varint(*self as u128, buf)
+            }
+        }
+
+        impl CompactDecode for $ty {
+            fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+                decode_varint(bytes, <$ty>::BITS).map(|value| value as $ty)
+            }
+        }
+    )*};
+}
+
+impl_compact_unsigned!(u16, u32, u64, u128, usize);
+
+macro_rules! impl_compact_signed {
+    ($($ty:ty => $unsigned:ty),*) => {$(
+        impl CompactEncode for $ty {
+            fn encode_compact(&self, buf: &mut Vec<u8>) {
+                let zigzag = ((*self << 1) ^ (*self >> (<$ty>::BITS - 1))) as $unsigned;
+                zigzag.encode_compact(buf)
+            }
+        }
+
+        impl CompactDecode for $ty {
+            fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+                let zigzag = <$unsigned>::decode_compact(bytes)?;
+                Ok((zigzag >> 1) as $ty ^ -((zigzag & 1) as $ty))
+            }
+        }
+    )*};
+}
+
+impl_compact_signed!(i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+impl CompactEncode for u8 {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        buf.push(*self)
+    }
+}
+
+impl CompactDecode for u8 {
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(take(bytes, 1)?[0])
+    }
+}
+
+impl CompactEncode for i8 {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8)
+    }
+}
+
+impl CompactDecode for i8 {
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(take(bytes, 1)?[0] as i8)
+    }
+}
+
+impl CompactEncode for bool {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        buf.push(*self as u8)
+    }
+}
+
+impl CompactDecode for bool {
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take(bytes, 1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+}
+
+impl<T: CompactEncode + ?Sized> CompactEncode for &T {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        (**self).encode_compact(buf)
+    }
+}
+
+impl<T: CompactEncode> CompactEncode for [T] {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        self.len().encode_compact(buf);
+        for item in self {
+            item.encode_compact(buf);
+        }
+    }
+}
+
+impl<T: CompactEncode> CompactEncode for Vec<T> {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        self.as_slice().encode_compact(buf)
+    }
+}
+
+impl<T: CompactDecode> CompactDecode for Vec<T> {
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = usize::decode_compact(bytes)?;
+        // Every item takes at least one byte, so the length can't exceed the input.
+        if len > bytes.len() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        (0..len).map(|_| T::decode_compact(bytes)).collect()
+    }
+}
+
+impl<T: CompactEncode, const N: usize> CompactEncode for [T; N] {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        for item in self {
+            item.encode_compact(buf);
+        }
+    }
+}
+
+impl<T: CompactDecode, const N: usize> CompactDecode for [T; N] {
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let items = (0..N)
+            .map(|_| T::decode_compact(bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("Exactly `N` items are decoded")))
+    }
+}
+
+impl CompactEncode for str {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        self.as_bytes().encode_compact(buf)
+    }
+}
+
+impl CompactEncode for String {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        self.as_str().encode_compact(buf)
+    }
+}
+
+impl CompactDecode for String {
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = usize::decode_compact(bytes)?;
+        let utf8 = take(bytes, len)?;
+        core::str::from_utf8(utf8)
+            .map(Into::into)
+            .map_err(|_| DecodeError::InvalidValue)
+    }
+}
+
+impl<T: CompactEncode> CompactEncode for Option<T> {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.push(0),
+            Some(value) => {
+                buf.push(1);
+                value.encode_compact(buf);
+            }
+        }
+    }
+}
+
+impl<T: CompactDecode> CompactDecode for Option<T> {
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match bool::decode_compact(bytes)? {
+            false => Ok(None),
+            true => T::decode_compact(bytes).map(Some),
+        }
+    }
+}
+
+macro_rules! impl_compact_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: CompactEncode),+> CompactEncode for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode_compact(&self, buf: &mut Vec<u8>) {
+                let ($($name,)+) = self;
+                $($name.encode_compact(buf);)+
+            }
+        }
+
+        impl<$($name: CompactDecode),+> CompactDecode for ($($name,)+) {
+            fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+                Ok(($($name::decode_compact(bytes)?,)+))
+            }
+        }
+    };
+}
+
+impl_compact_tuple!(A);
+impl_compact_tuple!(A, B);
+impl_compact_tuple!(A, B, C);
+impl_compact_tuple!(A, B, C, D);
@@ -0,0 +1,44 @@
+use crate::{codec::DecodeError, merkle::MerkleError, VersionError};
+use core::fmt;
+
+/// The error of the storages provided by this crate. Storages that wrap other storages require the
+/// error of the wrapped storage to be convertible from the errors they produce themselves, so it is
+/// a convenient error type for custom backends as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StorageError {
+    /// The stored bytes don't represent a value of the table.
+    Codec(DecodeError),
+    /// The Merkle tree stored in the storage is corrupted.
+    Merkle(MerkleError),
+    /// The versioned storage can't commit or serve the height.
+    Version(VersionError),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Codec(error) => write!(f, "failed to decode the stored value: {error}"),
+            Self::Merkle(error) => write!(f, "the Merkle tree is corrupted: {error}"),
+            Self::Version(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl From<DecodeError> for StorageError {
+    fn from(error: DecodeError) -> Self {
+        Self::Codec(error)
+    }
+}
+
+impl From<MerkleError> for StorageError {
+    fn from(error: MerkleError) -> Self {
+        Self::Merkle(error)
+    }
+}
+
+impl From<VersionError> for StorageError {
+    fn from(error: VersionError) -> Self {
+        Self::Version(error)
+    }
+}
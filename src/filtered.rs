@@ -0,0 +1,341 @@
+use crate::{
+    BoxedIter, IterDirection, KVItem, Mappable, MerkleMultiproofStorage, MerkleProofStorage,
+    MerkleRoot, MerkleRootStorage, MerkleSumRootStorage, StorageBatchMutate, StorageInspect,
+    StorageIterate, StorageMutate, StorageWrite,
+};
+use alloc::{borrow::Cow, collections::BTreeMap, vec, vec::Vec};
+use core::{
+    any::TypeId,
+    hash::{Hash, Hasher},
+    ops::Bound,
+};
+
+/// The wrapper around the storage that answers lookups of absent keys without touching the
+/// storage, using the Bloom filter over the keys of every table.
+///
+/// The filter of the table is built from the iteration over the table with the
+/// [`build`](Self::build), usually at startup, and updated on every insert through the wrapper.
+/// Once built, `get` and `contains_key` of keys the filter doesn't contain return nothing right
+/// away, other lookups and lookups in tables without the filter go to the storage. Removed keys
+/// stay in the filter, so they cost a lookup of the storage as before.
+///
+/// The filter keeps the size chosen by the build, `bits_per_key` bits for every key of the table,
+/// so its false positive rate grows as keys are inserted. Tables that grow significantly should
+/// be rebuilt from time to time. The underlying storage shouldn't be modified while wrapped.
+/// Iterations and the merkle methods go to the storage as is, so the tables the storage writes
+/// by itself, like the nodes of the merkle trees, shouldn't be filtered.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{FilteredStorage, Mappable, MemoryStorage, StorageAsMut};
+///
+/// pub struct Coins;
+///
+/// impl Mappable for Coins {
+///     type Key = [u8; 32];
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = MemoryStorage::new();
+/// for id in 0..100 {
+///     storage.storage::<Coins>().insert(&[id; 32], &10).unwrap();
+/// }
+///
+/// let mut filtered = FilteredStorage::new(&mut storage, 10);
+/// filtered.build::<Coins>().unwrap();
+/// filtered.storage::<Coins>().insert(&[200; 32], &10).unwrap();
+///
+/// assert!(filtered.storage::<Coins>().contains_key(&[7; 32]).unwrap());
+/// assert!(filtered.storage::<Coins>().contains_key(&[200; 32]).unwrap());
+/// assert!(!filtered.storage::<Coins>().contains_key(&[201; 32]).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FilteredStorage<S> {
+    storage: S,
+    bits_per_key: usize,
+    filters: BTreeMap<TypeId, BloomFilter>,
+}
+
+impl<S> FilteredStorage<S> {
+    /// Wrap the `storage` without filters. Filters of tables are built with `bits_per_key` bits
+    /// for every key, about 1% of false positives for 10 bits.
+    pub fn new(storage: S, bits_per_key: usize) -> Self {
+        Self {
+            storage,
+            bits_per_key: bits_per_key.max(1),
+            filters: BTreeMap::new(),
+        }
+    }
+
+    /// Return the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Unwrap the underlying storage, dropping the filters.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Build the filter of the `Type` from the iteration over all its keys, replacing the
+    /// existing filter.
+    pub fn build<Type>(&mut self) -> Result<(), S::Error>
+    where
+        Type: Mappable + 'static,
+        Type::Key: Hash,
+        S: StorageIterate<Type>,
+    {
+        // The keys are iterated twice, to size the filter and to fill it, instead of being
+        // collected, since the tables worth filtering are large.
+        let mut keys = 0;
+        for item in self.storage.iter_all(IterDirection::Forward) {
+            item?;
+            keys += 1;
+        }
+        let mut filter = BloomFilter::new(keys, self.bits_per_key);
+        for item in self.storage.iter_all(IterDirection::Forward) {
+            filter.insert(&item?.0);
+        }
+        self.filters.insert(TypeId::of::<Type>(), filter);
+        Ok(())
+    }
+
+    /// Return `true` if the filter of the `Type` is built.
+    pub fn is_built<Type: Mappable + 'static>(&self) -> bool {
+        self.filters.contains_key(&TypeId::of::<Type>())
+    }
+
+    /// Return `true` if the filter of the `Type` rules out the `key`.
+    fn is_absent<Type>(&self, key: &Type::Key) -> bool
+    where
+        Type: Mappable + 'static,
+        Type::Key: Hash,
+    {
+        self.filters
+            .get(&TypeId::of::<Type>())
+            .is_some_and(|filter| !filter.may_contain(key))
+    }
+}
+
+/// The Bloom filter with the `hashes` bit positions per key derived from two hashes of the key.
+#[derive(Debug, Clone)]
+struct BloomFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// The smallest filter, so the filters of empty tables still accept inserts.
+    const MIN_BITS: usize = 1024;
+
+    fn new(keys: usize, bits_per_key: usize) -> Self {
+        let bits = keys.saturating_mul(bits_per_key).max(Self::MIN_BITS);
+        // The optimal number of hashes is `ln 2` of the bits per key.
+        let hashes = (bits_per_key * 69 / 100).clamp(1, 30) as u32;
+        Self {
+            bits: vec![0; bits.div_ceil(64)],
+            hashes,
+        }
+    }
+
+    fn insert<K: Hash + ?Sized>(&mut self, key: &K) {
+        for bit in self.positions(key) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    fn may_contain<K: Hash + ?Sized>(&self, key: &K) -> bool {
+        self.positions(key)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn positions<K: Hash + ?Sized>(&self, key: &K) -> impl Iterator<Item = usize> {
+        let mut hasher = Fnv1a::default();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+        let (first, second) = (mix(hash), mix(hash ^ 0x9e37_79b9_7f4a_7c15) | 1);
+        let len = (self.bits.len() * 64) as u64;
+        (0..self.hashes as u64)
+            .map(move |i| (first.wrapping_add(i.wrapping_mul(second)) % len) as usize)
+    }
+}
+
+/// The 64-bit FNV-1a hasher of keys.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Spread the bits of the FNV hash, the SplitMix64 finalizer.
+fn mix(mut hash: u64) -> u64 {
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+impl<Type, S> StorageInspect<Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: StorageInspect<Type>,
+{
+    type Error = S::Error;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        if self.is_absent::<Type>(key) {
+            return Ok(None);
+        }
+        self.storage.get(key)
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        if self.is_absent::<Type>(key) {
+            return Ok(false);
+        }
+        self.storage.contains_key(key)
+    }
+}
+
+impl<Type, S> StorageMutate<Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: StorageMutate<Type>,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        // The key goes into the filter first, so the failed insert can't hide a written value.
+        if let Some(filter) = self.filters.get_mut(&TypeId::of::<Type>()) {
+            filter.insert(key);
+        }
+        self.storage.insert(key, value)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        if self.is_absent::<Type>(key) {
+            return Ok(None);
+        }
+        self.storage.remove(key)
+    }
+}
+
+impl<Type, S> StorageWrite<Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: StorageMutate<Type>,
+{
+}
+
+impl<Type, S> StorageBatchMutate<Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: StorageMutate<Type>,
+{
+}
+
+impl<Type, S> StorageIterate<Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: StorageIterate<Type>,
+{
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        self.storage.iter_range(start, end, direction)
+    }
+
+    fn iter_all(&self, direction: IterDirection) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        self.storage.iter_all(direction)
+    }
+
+    fn iter_prefix<'a>(
+        &'a self,
+        prefix: &[u8],
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, Self::Error>>
+    where
+        Type::Key: AsRef<[u8]> + 'a,
+        Type::GetValue: 'a,
+        Self::Error: 'a,
+    {
+        self.storage.iter_prefix(prefix, direction)
+    }
+}
+
+impl<Key, Type, S> MerkleRootStorage<Key, Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: MerkleRootStorage<Key, Type>,
+{
+    fn root(&mut self, key: &Key) -> Result<MerkleRoot, Self::Error> {
+        self.storage.root(key)
+    }
+}
+
+impl<Key, Type, S> MerkleSumRootStorage<Key, Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: MerkleSumRootStorage<Key, Type>,
+{
+    fn root_and_sum(&mut self, key: &Key) -> Result<(MerkleRoot, u64), Self::Error> {
+        self.storage.root_and_sum(key)
+    }
+}
+
+impl<Key, Type, S> MerkleProofStorage<Key, Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: MerkleProofStorage<Key, Type>,
+{
+    type Proof = S::Proof;
+
+    fn prove(&mut self, key: &Key, storage_key: &Type::Key) -> Result<Self::Proof, Self::Error> {
+        self.storage.prove(key, storage_key)
+    }
+}
+
+impl<Key, Type, S> MerkleMultiproofStorage<Key, Type> for FilteredStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Hash,
+    S: MerkleMultiproofStorage<Key, Type>,
+{
+    type Multiproof = S::Multiproof;
+
+    fn prove_many(
+        &mut self,
+        key: &Key,
+        storage_keys: &[Type::Key],
+    ) -> Result<Self::Multiproof, Self::Error> {
+        self.storage.prove_many(key, storage_keys)
+    }
+}
@@ -1,8 +1,14 @@
 use crate::{
-    Mappable, MerkleRoot, MerkleRootStorage, StorageInspect, StorageMut, StorageMutate, StorageRef,
+    kv_store::{IterableKeyValueStore, KeyValueItem, KeyValueStore},
+    AsyncStorageInspect, AsyncStorageMutate, BoxedIter, Changes, IterDirection, KVItem, Mappable,
+    MerkleMultiproofStorage, MerkleProofStorage, MerkleRoot, MerkleRootStorage,
+    MerkleSumRootStorage, StorageBatchMutate, StorageInspect, StorageIterate, StorageMut,
+    StorageMutate, StorageRef, StorageWrite,
 };
-use alloc::borrow::Cow;
+use alloc::{borrow::Cow, vec::Vec};
+use core::ops::Bound;
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, T: StorageInspect<Type> + ?Sized, Type: Mappable> StorageInspect<Type> for &'a T {
     type Error = T::Error;
 
@@ -15,6 +21,7 @@ impl<'a, T: StorageInspect<Type> + ?Sized, Type: Mappable> StorageInspect<Type>
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, T: StorageInspect<Type> + ?Sized, Type: Mappable> StorageInspect<Type> for &'a mut T {
     type Error = T::Error;
 
@@ -27,6 +34,7 @@ impl<'a, T: StorageInspect<Type> + ?Sized, Type: Mappable> StorageInspect<Type>
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, T: StorageMutate<Type> + ?Sized, Type: Mappable> StorageMutate<Type> for &'a mut T {
     fn insert(
         &mut self,
@@ -41,6 +49,154 @@ impl<'a, T: StorageMutate<Type> + ?Sized, Type: Mappable> StorageMutate<Type> fo
     }
 }
 
+impl<T: AsyncStorageInspect<Type> + ?Sized, Type: Mappable> AsyncStorageInspect<Type> for &T {
+    type Error = T::Error;
+
+    async fn get<'a>(
+        &'a self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'a, Type::GetValue>>, Self::Error>
+    where
+        Type::GetValue: 'a,
+    {
+        <T as AsyncStorageInspect<Type>>::get(self, key).await
+    }
+
+    async fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        <T as AsyncStorageInspect<Type>>::contains_key(self, key).await
+    }
+}
+
+impl<T: AsyncStorageInspect<Type> + ?Sized, Type: Mappable> AsyncStorageInspect<Type> for &mut T {
+    type Error = T::Error;
+
+    async fn get<'a>(
+        &'a self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'a, Type::GetValue>>, Self::Error>
+    where
+        Type::GetValue: 'a,
+    {
+        <T as AsyncStorageInspect<Type>>::get(self, key).await
+    }
+
+    async fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        <T as AsyncStorageInspect<Type>>::contains_key(self, key).await
+    }
+}
+
+impl<T: AsyncStorageMutate<Type> + ?Sized, Type: Mappable> AsyncStorageMutate<Type> for &mut T {
+    async fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        <T as AsyncStorageMutate<Type>>::insert(self, key, value).await
+    }
+
+    async fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        <T as AsyncStorageMutate<Type>>::remove(self, key).await
+    }
+}
+
+impl<T: StorageWrite<Type> + ?Sized, Type: Mappable> StorageWrite<Type> for &mut T {
+    fn write(&mut self, key: &Type::Key, value: &Type::SetValue) -> Result<(), Self::Error> {
+        <T as StorageWrite<Type>>::write(self, key, value)
+    }
+
+    fn replace(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        <T as StorageWrite<Type>>::replace(self, key, value)
+    }
+
+    fn take(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        <T as StorageWrite<Type>>::take(self, key)
+    }
+
+    fn delete(&mut self, key: &Type::Key) -> Result<(), Self::Error> {
+        <T as StorageWrite<Type>>::delete(self, key)
+    }
+}
+
+impl<T: StorageBatchMutate<Type> + ?Sized, Type: Mappable> StorageBatchMutate<Type> for &mut T {
+    fn insert_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = (&'a Type::Key, &'a Type::SetValue)>,
+        Type::Key: 'a,
+        Type::SetValue: 'a,
+    {
+        <T as StorageBatchMutate<Type>>::insert_batch(self, set)
+    }
+
+    fn remove_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = &'a Type::Key>,
+        Type::Key: 'a,
+    {
+        <T as StorageBatchMutate<Type>>::remove_batch(self, set)
+    }
+}
+
+impl<T: StorageIterate<Type> + ?Sized, Type: Mappable> StorageIterate<Type> for &T {
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        <T as StorageIterate<Type>>::iter_range(self, start, end, direction)
+    }
+
+    fn iter_all(&self, direction: IterDirection) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        <T as StorageIterate<Type>>::iter_all(self, direction)
+    }
+
+    fn iter_prefix<'a>(
+        &'a self,
+        prefix: &[u8],
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, Self::Error>>
+    where
+        Type::Key: AsRef<[u8]> + 'a,
+        Type::GetValue: 'a,
+        Self::Error: 'a,
+    {
+        <T as StorageIterate<Type>>::iter_prefix(self, prefix, direction)
+    }
+}
+
+impl<T: StorageIterate<Type> + ?Sized, Type: Mappable> StorageIterate<Type> for &mut T {
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        <T as StorageIterate<Type>>::iter_range(self, start, end, direction)
+    }
+
+    fn iter_all(&self, direction: IterDirection) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        <T as StorageIterate<Type>>::iter_all(self, direction)
+    }
+
+    fn iter_prefix<'a>(
+        &'a self,
+        prefix: &[u8],
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, Self::Error>>
+    where
+        Type::Key: AsRef<[u8]> + 'a,
+        Type::GetValue: 'a,
+        Self::Error: 'a,
+    {
+        <T as StorageIterate<Type>>::iter_prefix(self, prefix, direction)
+    }
+}
+
+#[allow(clippy::needless_lifetimes)]
 impl<'a, T: MerkleRootStorage<Key, Type> + ?Sized, Key, Type: Mappable> MerkleRootStorage<Key, Type>
     for &'a mut T
 {
@@ -49,6 +205,87 @@ impl<'a, T: MerkleRootStorage<Key, Type> + ?Sized, Key, Type: Mappable> MerkleRo
     }
 }
 
+impl<T: MerkleSumRootStorage<Key, Type> + ?Sized, Key, Type: Mappable>
+    MerkleSumRootStorage<Key, Type> for &mut T
+{
+    fn root_and_sum(&mut self, key: &Key) -> Result<(MerkleRoot, u64), Self::Error> {
+        <T as MerkleSumRootStorage<Key, Type>>::root_and_sum(self, key)
+    }
+}
+
+impl<T: MerkleProofStorage<Key, Type> + ?Sized, Key, Type: Mappable> MerkleProofStorage<Key, Type>
+    for &mut T
+{
+    type Proof = T::Proof;
+
+    fn prove(&mut self, key: &Key, storage_key: &Type::Key) -> Result<Self::Proof, Self::Error> {
+        <T as MerkleProofStorage<Key, Type>>::prove(self, key, storage_key)
+    }
+}
+
+impl<T: MerkleMultiproofStorage<Key, Type> + ?Sized, Key, Type: Mappable>
+    MerkleMultiproofStorage<Key, Type> for &mut T
+{
+    type Multiproof = T::Multiproof;
+
+    fn prove_many(
+        &mut self,
+        key: &Key,
+        storage_keys: &[Type::Key],
+    ) -> Result<Self::Multiproof, Self::Error> {
+        <T as MerkleMultiproofStorage<Key, Type>>::prove_many(self, key, storage_keys)
+    }
+}
+
+impl<T: KeyValueStore + ?Sized> KeyValueStore for &mut T {
+    type Error = T::Error;
+
+    fn get(&self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        T::get(self, column, key)
+    }
+
+    fn exists(&self, column: u32, key: &[u8]) -> Result<bool, Self::Error> {
+        T::exists(self, column, key)
+    }
+
+    fn put(&mut self, column: u32, key: &[u8], value: &[u8]) -> Result<(), Self::Error> {
+        T::put(self, column, key, value)
+    }
+
+    fn replace(
+        &mut self,
+        column: u32,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        T::replace(self, column, key, value)
+    }
+
+    fn delete(&mut self, column: u32, key: &[u8]) -> Result<(), Self::Error> {
+        T::delete(self, column, key)
+    }
+
+    fn take(&mut self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        T::take(self, column, key)
+    }
+
+    fn apply(&mut self, changes: &Changes) -> Result<(), Self::Error> {
+        T::apply(self, changes)
+    }
+}
+
+impl<T: IterableKeyValueStore + ?Sized> IterableKeyValueStore for &mut T {
+    fn iter_column(
+        &self,
+        column: u32,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KeyValueItem<Self::Error>> {
+        T::iter_column(self, column, start, end, direction)
+    }
+}
+
 impl<'a, T: StorageInspect<Type>, Type: Mappable> StorageRef<'a, T, Type> {
     #[inline(always)]
     pub fn get(self, key: &Type::Key) -> Result<Option<Cow<'a, Type::GetValue>>, T::Error> {
@@ -61,6 +298,37 @@ impl<'a, T: StorageInspect<Type>, Type: Mappable> StorageRef<'a, T, Type> {
     }
 }
 
+impl<'a, T: StorageIterate<Type>, Type: Mappable> StorageRef<'a, T, Type> {
+    #[inline(always)]
+    pub fn iter_range(
+        self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, T::Error>> {
+        self.0.iter_range(start, end, direction)
+    }
+
+    #[inline(always)]
+    pub fn iter_all(self, direction: IterDirection) -> BoxedIter<'a, KVItem<Type, T::Error>> {
+        self.0.iter_all(direction)
+    }
+
+    #[inline(always)]
+    pub fn iter_prefix(
+        self,
+        prefix: &[u8],
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, T::Error>>
+    where
+        Type::Key: AsRef<[u8]> + 'a,
+        Type::GetValue: 'a,
+        T::Error: 'a,
+    {
+        self.0.iter_prefix(prefix, direction)
+    }
+}
+
 impl<'a, T: StorageInspect<Type>, Type: Mappable> StorageMut<'a, T, Type> {
     #[inline(always)]
     pub fn get(self, key: &Type::Key) -> Result<Option<Cow<'a, Type::GetValue>>, T::Error> {
@@ -75,6 +343,40 @@ impl<'a, T: StorageInspect<Type>, Type: Mappable> StorageMut<'a, T, Type> {
     }
 }
 
+impl<'a, T: StorageIterate<Type>, Type: Mappable> StorageMut<'a, T, Type> {
+    #[inline(always)]
+    pub fn iter_range(
+        self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, T::Error>> {
+        let self_: &'a T = self.0;
+        self_.iter_range(start, end, direction)
+    }
+
+    #[inline(always)]
+    pub fn iter_all(self, direction: IterDirection) -> BoxedIter<'a, KVItem<Type, T::Error>> {
+        let self_: &'a T = self.0;
+        self_.iter_all(direction)
+    }
+
+    #[inline(always)]
+    pub fn iter_prefix(
+        self,
+        prefix: &[u8],
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, T::Error>>
+    where
+        Type::Key: AsRef<[u8]> + 'a,
+        Type::GetValue: 'a,
+        T::Error: 'a,
+    {
+        let self_: &'a T = self.0;
+        self_.iter_prefix(prefix, direction)
+    }
+}
+
 impl<'a, T: StorageMutate<Type>, Type: Mappable> StorageMut<'a, T, Type> {
     #[inline(always)]
     pub fn insert(
@@ -91,6 +393,53 @@ impl<'a, T: StorageMutate<Type>, Type: Mappable> StorageMut<'a, T, Type> {
     }
 }
 
+impl<'a, T: StorageWrite<Type>, Type: Mappable> StorageMut<'a, T, Type> {
+    #[inline(always)]
+    pub fn write(self, key: &Type::Key, value: &Type::SetValue) -> Result<(), T::Error> {
+        self.0.write(key, value)
+    }
+
+    #[inline(always)]
+    pub fn replace(
+        self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, T::Error> {
+        self.0.replace(key, value)
+    }
+
+    #[inline(always)]
+    pub fn take(self, key: &Type::Key) -> Result<Option<Type::GetValue>, T::Error> {
+        self.0.take(key)
+    }
+
+    #[inline(always)]
+    pub fn delete(self, key: &Type::Key) -> Result<(), T::Error> {
+        self.0.delete(key)
+    }
+}
+
+impl<'a, T: StorageBatchMutate<Type>, Type: Mappable> StorageMut<'a, T, Type> {
+    #[inline(always)]
+    pub fn insert_batch<'b, Iter>(self, set: Iter) -> Result<(), T::Error>
+    where
+        Iter: IntoIterator<Item = (&'b Type::Key, &'b Type::SetValue)>,
+        Type::Key: 'b,
+        Type::SetValue: 'b,
+    {
+        self.0.insert_batch(set)
+    }
+
+    #[inline(always)]
+    pub fn remove_batch<'b, Iter>(self, set: Iter) -> Result<(), T::Error>
+    where
+        Iter: IntoIterator<Item = &'b Type::Key>,
+        Type::Key: 'b,
+    {
+        self.0.remove_batch(set)
+    }
+}
+
 impl<'a, T: StorageMutate<Type>, Type: Mappable> StorageMut<'a, T, Type> {
     #[inline(always)]
     pub fn root<Key>(self, key: &Key) -> Result<MerkleRoot, T::Error>
@@ -99,4 +448,32 @@ impl<'a, T: StorageMutate<Type>, Type: Mappable> StorageMut<'a, T, Type> {
     {
         self.0.root(key)
     }
+
+    #[inline(always)]
+    pub fn root_and_sum<Key>(self, key: &Key) -> Result<(MerkleRoot, u64), T::Error>
+    where
+        T: MerkleSumRootStorage<Key, Type>,
+    {
+        self.0.root_and_sum(key)
+    }
+
+    #[inline(always)]
+    pub fn prove<Key>(self, key: &Key, storage_key: &Type::Key) -> Result<T::Proof, T::Error>
+    where
+        T: MerkleProofStorage<Key, Type>,
+    {
+        self.0.prove(key, storage_key)
+    }
+
+    #[inline(always)]
+    pub fn prove_many<Key>(
+        self,
+        key: &Key,
+        storage_keys: &[Type::Key],
+    ) -> Result<T::Multiproof, T::Error>
+    where
+        T: MerkleMultiproofStorage<Key, Type>,
+    {
+        self.0.prove_many(key, storage_keys)
+    }
 }
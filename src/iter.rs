@@ -0,0 +1,136 @@
+//! Iterators shared by the implementations of [`StorageIterate`](crate::StorageIterate).
+
+use crate::IterDirection;
+use alloc::{
+    boxed::Box,
+    collections::{btree_map, BTreeMap},
+    vec::Vec,
+};
+use core::{borrow::Borrow, cmp::Ordering, iter::Peekable, ops::Bound};
+
+/// Yields the items of the inner iterator with keys starting with the prefix. Errors are passed
+/// through.
+pub(crate) struct PrefixFilter<I> {
+    iter: I,
+    prefix: Vec<u8>,
+}
+
+impl<I> PrefixFilter<I> {
+    pub(crate) fn new(iter: I, prefix: &[u8]) -> Self {
+        Self {
+            iter,
+            prefix: prefix.to_vec(),
+        }
+    }
+}
+
+impl<I, K, V, E> Iterator for PrefixFilter<I>
+where
+    I: Iterator<Item = Result<(K, V), E>>,
+    K: AsRef<[u8]>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let prefix = &self.prefix;
+        self.iter.find(|item| match item {
+            Ok((key, _)) => key.as_ref().starts_with(prefix),
+            Err(_) => true,
+        })
+    }
+}
+
+/// Return the range of the `map` within the bounds, or `None` if the bounds don't form a valid
+/// range, in which case `BTreeMap::range` would panic.
+pub(crate) fn btree_range<'a, K, V, Q>(
+    map: &'a BTreeMap<K, V>,
+    start: Bound<&Q>,
+    end: Bound<&Q>,
+) -> Option<btree_map::Range<'a, K, V>>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let valid = match (start, end) {
+        (Bound::Included(start), Bound::Included(end)) => start <= end,
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) => start < end,
+        _ => true,
+    };
+    valid.then(|| map.range::<Q, _>((start, end)))
+}
+
+/// Iterate over the `range` in the `direction`.
+pub(crate) fn directed<'a, I>(
+    range: I,
+    direction: IterDirection,
+) -> Box<dyn Iterator<Item = I::Item> + 'a>
+where
+    I: DoubleEndedIterator + 'a,
+{
+    match direction {
+        IterDirection::Forward => Box::new(range),
+        IterDirection::Reverse => Box::new(range.rev()),
+    }
+}
+
+/// Merges the iterator over the underlying storage with the changes on top of it. Both should be
+/// ordered by the key in the same `direction`. A change of `None` hides the key of the underlying
+/// storage.
+pub(crate) struct MergedIter<'a, K, V, I: Iterator> {
+    inner: Peekable<I>,
+    changes: Peekable<alloc::vec::IntoIter<(&'a K, &'a Option<V>)>>,
+    direction: IterDirection,
+}
+
+impl<'a, K, V, I: Iterator> MergedIter<'a, K, V, I> {
+    pub(crate) fn new(
+        inner: I,
+        changes: Vec<(&'a K, &'a Option<V>)>,
+        direction: IterDirection,
+    ) -> Self {
+        Self {
+            inner: inner.peekable(),
+            changes: changes.into_iter().peekable(),
+            direction,
+        }
+    }
+}
+
+impl<K, V, E, I> Iterator for MergedIter<'_, K, V, I>
+where
+    K: Ord + Clone,
+    V: Clone,
+    I: Iterator<Item = Result<(K, V), E>>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let ordering = match (self.inner.peek(), self.changes.peek()) {
+                (Some(Err(_)), _) | (Some(Ok(_)), None) => return self.inner.next(),
+                (None, None) => return None,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(Ok((key, _))), Some((change, _))) => match self.direction {
+                    IterDirection::Forward => key.cmp(change),
+                    IterDirection::Reverse => change.cmp(&key),
+                },
+            };
+
+            match ordering {
+                Ordering::Less => return self.inner.next(),
+                Ordering::Equal => {
+                    self.inner.next();
+                }
+                Ordering::Greater => {}
+            }
+
+            let (key, value) = self.changes.next().expect("The change was peeked above");
+            if let Some(value) = value {
+                return Some(Ok((key.clone(), value.clone())));
+            }
+        }
+    }
+}
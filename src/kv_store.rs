@@ -0,0 +1,77 @@
+use crate::{BoxedIter, Changes, IterDirection};
+use alloc::vec::Vec;
+use core::ops::Bound;
+
+/// The item of the [`IterableKeyValueStore`] iterators: the encoded key and value or the error.
+pub type KeyValueItem<Error> = Result<(Vec<u8>, Vec<u8>), Error>;
+
+/// The low-level storage of bytes split into columns. Each column is an independent mapping from
+/// the key bytes to the value bytes.
+///
+/// It is enough to implement this trait to serve every [`Table`](crate::Table) with the
+/// [`StructuredStorage`](crate::StructuredStorage).
+pub trait KeyValueStore {
+    type Error;
+
+    /// Retrieve the value stored under the `key` in the `column`.
+    fn get(&self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Return `true` if there is a value stored under the `key` in the `column`.
+    fn exists(&self, column: u32, key: &[u8]) -> Result<bool, Self::Error> {
+        Ok(self.get(column, key)?.is_some())
+    }
+
+    /// Store the `value` under the `key` in the `column`, overwriting the existing value if any.
+    fn put(&mut self, column: u32, key: &[u8], value: &[u8]) -> Result<(), Self::Error>;
+
+    /// Store the `value` under the `key` in the `column` and return the replaced value.
+    fn replace(
+        &mut self,
+        column: u32,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        let previous = self.get(column, key)?;
+        self.put(column, key, value)?;
+        Ok(previous)
+    }
+
+    /// Remove the value stored under the `key` in the `column`. A missing key is ignored.
+    fn delete(&mut self, column: u32, key: &[u8]) -> Result<(), Self::Error>;
+
+    /// Remove the value stored under the `key` in the `column` and return it.
+    fn take(&mut self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        let previous = self.get(column, key)?;
+        if previous.is_some() {
+            self.delete(column, key)?;
+        }
+        Ok(previous)
+    }
+
+    /// Apply the `changes` to the store.
+    ///
+    /// The default implementation writes the changes one by one, so the failed write leaves a part
+    /// of the changes applied. Stores with atomic batches of writes should override it.
+    fn apply(&mut self, changes: &Changes) -> Result<(), Self::Error> {
+        for (column, key, value) in changes.iter() {
+            match value {
+                Some(value) => self.put(column, key, value)?,
+                None => self.delete(column, key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The [`KeyValueStore`] that can iterate over a column in the lexicographic order of keys.
+pub trait IterableKeyValueStore: KeyValueStore {
+    /// Iterate over the keys and values of the `column` with keys within the `start` and `end`
+    /// bounds in the `direction` order of keys.
+    fn iter_column(
+        &self,
+        column: u32,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KeyValueItem<Self::Error>>;
+}
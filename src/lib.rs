@@ -1,10 +1,45 @@
 #![no_std]
 
+mod asynchronous;
+mod cached;
+mod changes;
+pub mod codec;
+mod error;
+mod filtered;
 mod impls;
+mod iter;
+pub mod kv_store;
+mod memory;
+pub mod merkle;
+mod recording;
+mod structured;
+mod table;
+mod transaction;
+mod versioned;
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
-use alloc::borrow::Cow;
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+};
+use core::{borrow::Borrow, ops::Bound};
+
+pub use asynchronous::{block_on, AsyncStorage, BlockingStorage};
+pub use cached::{CacheCapacity, CacheStats, CachedStorage, Weigher};
+pub use changes::{Changes, KeyValueOverlay};
+pub use codec::TableWithCodec;
+pub use error::StorageError;
+pub use filtered::FilteredStorage;
+pub use kv_store::{IterableKeyValueStore, KeyValueStore};
+pub use memory::{MemoryKeyValueStore, MemoryStorage};
+pub use recording::{Accesses, RecordingStorage};
+pub use structured::StructuredStorage;
+pub use table::{find_duplicate_column, Table, TableInfo};
+pub use transaction::{Savepoint, StorageTransaction};
+pub use versioned::{PruningPolicy, StorageAt, VersionError, VersionedStorage};
 
 /// Merkle root alias type
 pub type MerkleRoot = [u8; 32];
@@ -36,6 +71,11 @@ pub trait Mappable {
     type GetValue: Clone;
 }
 
+/// Borrow the `SetValue` from the `GetValue`, which is its owned form.
+pub(crate) fn borrow<T: ?Sized + ToOwned>(owned: &T::Owned) -> &T {
+    owned.borrow()
+}
+
 /// Base read storage trait for Fuel infrastructure.
 ///
 /// Generic should implement [`Mappable`] trait with all storage type information.
@@ -43,6 +83,7 @@ pub trait StorageInspect<Type: Mappable> {
     type Error;
 
     /// Retrieve `Cow<Value>` such as `Key->Value`.
+    #[allow(mismatched_lifetime_syntaxes)]
     fn get(&self, key: &Type::Key) -> Result<Option<Cow<Type::GetValue>>, Self::Error>;
 
     /// Return `true` if there is a `Key` mapping to a value in the storage.
@@ -70,6 +111,241 @@ pub trait StorageMutate<Type: Mappable>: StorageInspect<Type> {
     fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error>;
 }
 
+/// Modification of the storage without fetching the previous values. Database backends may
+/// override the default implementations to avoid reading before writing.
+///
+/// Generic should implement [`Mappable`] trait with all storage type information.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{Mappable, MemoryStorage, StorageAsMut};
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = MemoryStorage::new();
+/// storage.storage::<Balances>().write(&1, &10).unwrap();
+/// assert_eq!(storage.storage::<Balances>().replace(&1, &20), Ok(Some(10)));
+/// assert_eq!(storage.storage::<Balances>().take(&1), Ok(Some(20)));
+/// storage.storage::<Balances>().delete(&1).unwrap();
+/// ```
+pub trait StorageWrite<Type: Mappable>: StorageMutate<Type> {
+    /// Append `Key->Value` mapping to the storage, overwriting the existing value if any.
+    fn write(&mut self, key: &Type::Key, value: &Type::SetValue) -> Result<(), Self::Error> {
+        self.insert(key, value).map(|_| ())
+    }
+
+    /// Append `Key->Value` mapping to the storage and return the replaced value, the same as
+    /// [`StorageMutate::insert`].
+    fn replace(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        self.insert(key, value)
+    }
+
+    /// Remove `Key->Value` mapping from the storage and return the removed value, the same as
+    /// [`StorageMutate::remove`].
+    fn take(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        self.remove(key)
+    }
+
+    /// Remove `Key->Value` mapping from the storage. A missing key is ignored.
+    fn delete(&mut self, key: &Type::Key) -> Result<(), Self::Error> {
+        self.remove(key).map(|_| ())
+    }
+}
+
+/// Batch modification of the storage. Database backends may override the default implementations
+/// to translate the whole batch into one write without fetching the previous values.
+///
+/// Generic should implement [`Mappable`] trait with all storage type information.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{Mappable, MemoryStorage, StorageAsMut};
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = MemoryStorage::new();
+/// let balances = [(1, 10), (2, 20), (3, 30)];
+/// storage
+///     .storage::<Balances>()
+///     .insert_batch(balances.iter().map(|(key, value)| (key, value)))
+///     .unwrap();
+/// storage.storage::<Balances>().remove_batch(&[1, 3]).unwrap();
+///
+/// assert!(!storage.storage::<Balances>().contains_key(&1).unwrap());
+/// assert!(storage.storage::<Balances>().contains_key(&2).unwrap());
+/// ```
+pub trait StorageBatchMutate<Type: Mappable>: StorageMutate<Type> {
+    /// Append all `Key->Value` mappings from the `set` to the storage. Already existing keys are
+    /// overwritten.
+    fn insert_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = (&'a Type::Key, &'a Type::SetValue)>,
+        Type::Key: 'a,
+        Type::SetValue: 'a,
+    {
+        for (key, value) in set {
+            self.insert(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Remove `Key->Value` mappings for all keys from the `set`. Missing keys are ignored.
+    fn remove_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = &'a Type::Key>,
+        Type::Key: 'a,
+    {
+        for key in set {
+            self.remove(key)?;
+        }
+        Ok(())
+    }
+}
+
+/// The asynchronous counterpart of the [`StorageInspect`] for backends that wait for I/O, such as
+/// remote services. The [`AsyncStorage`] serves any [`StorageInspect`] through it, and the
+/// [`BlockingStorage`] serves it through the [`StorageInspect`].
+///
+/// Generic should implement [`Mappable`] trait with all storage type information.
+// The futures of the trait aren't required to be `Send`, so it stays usable by single-threaded and
+// `no_std` executors.
+#[allow(async_fn_in_trait)]
+pub trait AsyncStorageInspect<Type: Mappable> {
+    type Error;
+
+    /// Retrieve `Cow<Value>` such as `Key->Value`.
+    async fn get<'a>(
+        &'a self,
+        key: &Type::Key,
+    ) -> Result<Option<Cow<'a, Type::GetValue>>, Self::Error>
+    where
+        Type::GetValue: 'a;
+
+    /// Return `true` if there is a `Key` mapping to a value in the storage.
+    async fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error>;
+}
+
+/// The asynchronous counterpart of the [`StorageMutate`].
+///
+/// Generic should implement [`Mappable`] trait with all storage type information.
+#[allow(async_fn_in_trait)]
+pub trait AsyncStorageMutate<Type: Mappable>: AsyncStorageInspect<Type> {
+    /// Append `Key->Value` mapping to the storage.
+    ///
+    /// If `Key` was already mappped to a value, return the replaced value as `Ok(Some(Value))`. Return
+    /// `Ok(None)` otherwise.
+    async fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error>;
+
+    /// Remove `Key->Value` mapping from the storage.
+    ///
+    /// Return `Ok(Some(Value))` if the value was present. If the key wasn't found, return
+    /// `Ok(None)`.
+    async fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error>;
+}
+
+/// The order of keys in which the storage is iterated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IterDirection {
+    /// Iterate from the smallest key to the largest one.
+    #[default]
+    Forward,
+    /// Iterate from the largest key to the smallest one.
+    Reverse,
+}
+
+/// The boxed iterator returned by [`StorageIterate`].
+pub type BoxedIter<'a, T> = Box<dyn Iterator<Item = T> + 'a>;
+
+/// The item of the [`StorageIterate`] iterators: the owned `Key->Value` mapping or the error.
+pub type KVItem<Type, Error> =
+    Result<(<Type as Mappable>::Key, <Type as Mappable>::GetValue), Error>;
+
+/// Ordered iteration over the storage.
+///
+/// Generic should implement [`Mappable`] trait with all storage type information.
+///
+/// # Example
+///
+/// ```rust
+/// use core::ops::Bound;
+/// use fuel_storage::{IterDirection, Mappable, MemoryStorage, StorageAsMut};
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = MemoryStorage::new();
+/// for key in 0..5 {
+///     storage.storage::<Balances>().insert(&key, &(key as u64 * 10)).unwrap();
+/// }
+///
+/// let keys: Vec<_> = storage
+///     .storage::<Balances>()
+///     .iter_range(Bound::Included(&1), Bound::Excluded(&4), IterDirection::Reverse)
+///     .map(|item| item.unwrap().0)
+///     .collect();
+/// assert_eq!(keys, vec![3, 2, 1]);
+/// ```
+pub trait StorageIterate<Type: Mappable>: StorageInspect<Type> {
+    /// Iterate over `Key->Value` mappings with keys within the `start` and `end` bounds in the
+    /// `direction` order of keys.
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>>;
+
+    /// Iterate over all `Key->Value` mappings in the `direction` order of keys.
+    fn iter_all(&self, direction: IterDirection) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        self.iter_range(Bound::Unbounded, Bound::Unbounded, direction)
+    }
+
+    /// Iterate over `Key->Value` mappings with keys starting with the `prefix` in the `direction`
+    /// order of keys.
+    ///
+    /// The default implementation filters [`StorageIterate::iter_all`], so backends that know the
+    /// byte order of their keys should override it.
+    fn iter_prefix<'a>(
+        &'a self,
+        prefix: &[u8],
+        direction: IterDirection,
+    ) -> BoxedIter<'a, KVItem<Type, Self::Error>>
+    where
+        Type::Key: AsRef<[u8]> + 'a,
+        Type::GetValue: 'a,
+        Self::Error: 'a,
+    {
+        Box::new(iter::PrefixFilter::new(self.iter_all(direction), prefix))
+    }
+}
+
 /// Returns the merkle root for the `StorageType` per merkle `Key`. The type should implement the
 /// `StorageMutate` for the `StorageType`. Per one storage, it is possible to have several merkle trees
 /// under different `Key`.
@@ -84,6 +360,53 @@ where
     fn root(&mut self, key: &Key) -> Result<MerkleRoot, Self::Error>;
 }
 
+/// Returns the merkle root together with the sum of the `StorageType` values per merkle `Key`, for
+/// trees that commit to the totals of their values.
+pub trait MerkleSumRootStorage<Key, StorageType>: MerkleRootStorage<Key, StorageType>
+where
+    StorageType: Mappable,
+{
+    /// Return the merkle root and the sum of the values of the stored `Type` in the storage.
+    fn root_and_sum(&mut self, key: &Key) -> Result<(MerkleRoot, u64), Self::Error>;
+}
+
+/// Returns the merkle proof of the `StorageType` entry against the merkle root per merkle `Key`.
+/// The proof of the present entry proves its inclusion and the proof of the absent entry proves
+/// its exclusion, so both can be verified without the storage.
+pub trait MerkleProofStorage<Key, StorageType>: MerkleRootStorage<Key, StorageType>
+where
+    StorageType: Mappable,
+{
+    /// The proof type, verified by the function provided together with the implementation.
+    type Proof;
+
+    /// Return the proof of the entry with the `storage_key` against the merkle root of the `key`.
+    fn prove(
+        &mut self,
+        key: &Key,
+        storage_key: &StorageType::Key,
+    ) -> Result<Self::Proof, Self::Error>;
+}
+
+/// Returns the merkle multiproof of several `StorageType` entries against the merkle root per
+/// merkle `Key`. The multiproof shares the nodes common to the proofs of the entries, so it is
+/// smaller than the proofs of the entries taken separately.
+pub trait MerkleMultiproofStorage<Key, StorageType>: MerkleRootStorage<Key, StorageType>
+where
+    StorageType: Mappable,
+{
+    /// The multiproof type, verified by the function provided together with the implementation.
+    type Multiproof;
+
+    /// Return the multiproof of the entries with the `storage_keys`, present or absent, against
+    /// the merkle root of the `key`.
+    fn prove_many(
+        &mut self,
+        key: &Key,
+        storage_keys: &[StorageType::Key],
+    ) -> Result<Self::Multiproof, Self::Error>;
+}
+
 /// The wrapper around the storage that supports only methods from `StorageInspect`.
 pub struct StorageRef<'a, T: 'a + ?Sized, Type: Mappable>(&'a T, core::marker::PhantomData<Type>);
 
@@ -120,6 +443,7 @@ pub struct StorageRef<'a, T: 'a + ?Sized, Type: Mappable>(&'a T, core::marker::P
 /// ```
 pub trait StorageAsRef {
     #[inline(always)]
+    #[allow(mismatched_lifetime_syntaxes)]
     fn storage<Type>(&self) -> StorageRef<Self, Type>
     where
         Type: Mappable,
@@ -128,6 +452,7 @@ pub trait StorageAsRef {
     }
 }
 
+#[allow(clippy::extra_unused_lifetimes)]
 impl<'a, T> StorageAsRef for T {}
 
 /// The wrapper around the storage that supports methods from `StorageInspect` and `StorageMutate`.
@@ -171,6 +496,7 @@ pub struct StorageMut<'a, T: 'a + ?Sized, Type: Mappable>(
 /// ```
 pub trait StorageAsMut {
     #[inline(always)]
+    #[allow(mismatched_lifetime_syntaxes)]
     fn storage<Type>(&mut self) -> StorageMut<Self, Type>
     where
         Type: Mappable,
@@ -179,4 +505,5 @@ pub trait StorageAsMut {
     }
 }
 
+#[allow(clippy::extra_unused_lifetimes)]
 impl<'a, T> StorageAsMut for T {}
@@ -0,0 +1,280 @@
+use crate::{
+    iter,
+    kv_store::{IterableKeyValueStore, KeyValueItem, KeyValueStore},
+    BoxedIter, IterDirection, KVItem, Mappable, StorageBatchMutate, StorageError, StorageInspect,
+    StorageIterate, StorageMutate, StorageWrite,
+};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+    vec::Vec,
+};
+use core::{
+    any::{Any, TypeId},
+    convert::Infallible,
+    ops::Bound,
+};
+
+/// The in-memory storage that can hold any number of [`Mappable`] tables. Each table is a
+/// `BTreeMap` from the `Key` to the `GetValue`, and tables are distinguished by the type of the
+/// `Mappable`.
+///
+/// It is the reference implementation of [`StorageInspect`] and [`StorageMutate`]: the `get`
+/// returns `Cow::Borrowed` and `insert`/`remove` return the previous value.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{Mappable, MemoryStorage, StorageAsMut};
+///
+/// pub struct Contracts;
+///
+/// impl Mappable for Contracts {
+///     type Key = [u8; 32];
+///     type SetValue = [u8];
+///     type GetValue = Vec<u8>;
+/// }
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = MemoryStorage::new();
+///
+/// assert_eq!(storage.storage::<Balances>().insert(&123, &321), Ok(None));
+/// assert_eq!(storage.storage::<Balances>().insert(&123, &456), Ok(Some(321)));
+/// assert_eq!(storage.storage::<Contracts>().insert(&[0; 32], &[1, 2, 3]), Ok(None));
+///
+/// assert_eq!(storage.storage::<Balances>().get(&123).unwrap().unwrap().into_owned(), 456);
+/// assert!(!storage.storage::<Contracts>().contains_key(&[1; 32]).unwrap());
+/// assert_eq!(storage.storage::<Contracts>().remove(&[0; 32]), Ok(Some(vec![1, 2, 3])));
+/// ```
+#[derive(Default)]
+pub struct MemoryStorage {
+    tables: BTreeMap<TypeId, Box<dyn Any>>,
+}
+
+impl MemoryStorage {
+    /// Create an empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the table of the `Type` if at least one value was inserted into it.
+    pub fn table<Type>(&self) -> Option<&BTreeMap<Type::Key, Type::GetValue>>
+    where
+        Type: Mappable + 'static,
+        Type::Key: 'static,
+        Type::GetValue: 'static,
+    {
+        self.tables
+            .get(&TypeId::of::<Type>())
+            .and_then(|table| table.downcast_ref())
+    }
+
+    /// Return the mutable table of the `Type`, creating an empty one if it doesn't exist.
+    pub fn table_mut<Type>(&mut self) -> &mut BTreeMap<Type::Key, Type::GetValue>
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + 'static,
+        Type::GetValue: 'static,
+    {
+        self.tables
+            .entry(TypeId::of::<Type>())
+            .or_insert_with(|| Box::new(BTreeMap::<Type::Key, Type::GetValue>::new()))
+            .downcast_mut()
+            .expect("The table is always created with the type of the `Mappable`")
+    }
+}
+
+impl<Type> StorageInspect<Type> for MemoryStorage
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + 'static,
+    Type::GetValue: 'static,
+{
+    type Error = Infallible;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        Ok(self
+            .table::<Type>()
+            .and_then(|table| table.get(key))
+            .map(Cow::Borrowed))
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        Ok(self
+            .table::<Type>()
+            .map(|table| table.contains_key(key))
+            .unwrap_or(false))
+    }
+}
+
+impl<Type> StorageMutate<Type> for MemoryStorage
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: 'static,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        Ok(self
+            .table_mut::<Type>()
+            .insert(key.clone(), value.to_owned()))
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        Ok(self
+            .tables
+            .get_mut(&TypeId::of::<Type>())
+            .and_then(|table| table.downcast_mut::<BTreeMap<Type::Key, Type::GetValue>>())
+            .and_then(|table| table.remove(key)))
+    }
+}
+
+impl<Type> StorageWrite<Type> for MemoryStorage
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: 'static,
+{
+}
+
+impl<Type> StorageBatchMutate<Type> for MemoryStorage
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: 'static,
+{
+    fn insert_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = (&'a Type::Key, &'a Type::SetValue)>,
+        Type::Key: 'a,
+        Type::SetValue: 'a,
+    {
+        self.table_mut::<Type>().extend(
+            set.into_iter()
+                .map(|(key, value)| (key.clone(), value.to_owned())),
+        );
+        Ok(())
+    }
+}
+
+impl<Type> StorageIterate<Type> for MemoryStorage
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+{
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        let range = self
+            .table::<Type>()
+            .and_then(|table| iter::btree_range(table, start, end))
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| Ok((key.clone(), value.clone())));
+        iter::directed(range, direction)
+    }
+}
+
+/// The in-memory [`KeyValueStore`]. Each column is a `BTreeMap` from the key bytes to the value
+/// bytes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryKeyValueStore {
+    columns: BTreeMap<u32, BTreeMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl MemoryKeyValueStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the `column` if at least one value was stored in it.
+    pub fn column(&self, column: u32) -> Option<&BTreeMap<Vec<u8>, Vec<u8>>> {
+        self.columns.get(&column)
+    }
+}
+
+impl KeyValueStore for MemoryKeyValueStore {
+    type Error = StorageError;
+
+    fn get(&self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self
+            .columns
+            .get(&column)
+            .and_then(|column| column.get(key))
+            .cloned())
+    }
+
+    fn exists(&self, column: u32, key: &[u8]) -> Result<bool, Self::Error> {
+        Ok(self
+            .columns
+            .get(&column)
+            .map(|column| column.contains_key(key))
+            .unwrap_or(false))
+    }
+
+    fn put(&mut self, column: u32, key: &[u8], value: &[u8]) -> Result<(), Self::Error> {
+        self.replace(column, key, value).map(|_| ())
+    }
+
+    fn replace(
+        &mut self,
+        column: u32,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self
+            .columns
+            .entry(column)
+            .or_default()
+            .insert(key.to_vec(), value.to_vec()))
+    }
+
+    fn delete(&mut self, column: u32, key: &[u8]) -> Result<(), Self::Error> {
+        self.take(column, key).map(|_| ())
+    }
+
+    fn take(&mut self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self
+            .columns
+            .get_mut(&column)
+            .and_then(|column| column.remove(key)))
+    }
+}
+
+impl IterableKeyValueStore for MemoryKeyValueStore {
+    fn iter_column(
+        &self,
+        column: u32,
+        start: Bound<&[u8]>,
+        end: Bound<&[u8]>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KeyValueItem<Self::Error>> {
+        let range = self
+            .columns
+            .get(&column)
+            .and_then(|column| iter::btree_range(column, start, end))
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| Ok((key.clone(), value.clone())));
+        iter::directed(range, direction)
+    }
+}
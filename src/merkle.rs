@@ -0,0 +1,99 @@
+//! Merkle trees over the storage: the sparse Merkle tree that implements
+//! [`MerkleRootStorage`](crate::MerkleRootStorage) for tables, its [`Sum`] variant that commits to
+//! the totals of balances, and the append-only binary Merkle tree for ordered logs. The
+//! [`ProvenStorage`] serves the entries of the sparse Merkle tree from its proofs alone.
+//!
+//! Trees keep their nodes in tables of the same storage, so they are persisted, cached and
+//! committed together with the values they commit to. The hash function of the tree is pluggable
+//! through the [`MerkleHasher`]: the [`Sha256`] is used by default, and the [`Poseidon`] is
+//! cheaper to prove in zero-knowledge circuits.
+
+mod binary;
+mod multiproof;
+mod poseidon;
+mod proven;
+mod sha256;
+mod sparse;
+mod sum;
+mod witness;
+
+use crate::MerkleRoot;
+use core::fmt;
+
+pub use binary::{BinaryMerkleProof, BinaryMerkleTree};
+pub use multiproof::{verify_multiproof, SparseMerkleMultiproof};
+pub use poseidon::Poseidon;
+pub use proven::ProvenStorage;
+pub use sha256::Sha256;
+pub use sparse::{
+    verify, DigestOf, SparseDigest, SparseMerkleProof, SparseMerkleStorage, SparseMerkleTable,
+    SparseNode, SparseTreeKind, ValueHash,
+};
+pub use sum::Sum;
+pub use witness::{StateWitness, TreeWitness};
+
+/// The hash function of Merkle trees.
+pub trait MerkleHasher: Default {
+    /// Append the `data` to the hashed input.
+    fn update(&mut self, data: &[u8]);
+
+    /// Return the hash of the input.
+    fn finalize(self) -> MerkleRoot;
+
+    /// Return the hash of the `data`.
+    fn hash(data: &[u8]) -> MerkleRoot {
+        let mut hasher = Self::default();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+/// The error of Merkle trees stored in the storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MerkleError {
+    /// The node with the hash is referenced by the tree but missing in the storage.
+    MissingNode(MerkleRoot),
+    /// The node at the position is referenced by the tree but missing in the storage.
+    MissingPosition(u64),
+    /// The sum of the values in the sum tree overflows the `u64`.
+    SumOverflow,
+    /// The proof doesn't prove the entries against the root.
+    InvalidProof,
+    /// The entry at the path isn't covered by the proofs.
+    Unproven(MerkleRoot),
+    /// The branch of the sparse tree lies deeper than the paths have bits.
+    TooDeep,
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingNode(hash) => {
+                write!(f, "the node ")?;
+                write_hex(f, hash)?;
+                write!(f, " is missing in the storage")
+            }
+            Self::MissingPosition(position) => {
+                write!(
+                    f,
+                    "the node at the position {position} is missing in the storage"
+                )
+            }
+            Self::SumOverflow => write!(f, "the sum of the values overflows"),
+            Self::InvalidProof => write!(f, "the proof is invalid"),
+            Self::Unproven(path) => {
+                write!(f, "the entry at the path ")?;
+                write_hex(f, path)?;
+                write!(f, " isn't covered by the proofs")
+            }
+            Self::TooDeep => write!(f, "the branch lies deeper than the paths have bits"),
+        }
+    }
+}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for byte in bytes {
+        write!(f, "{byte:02x}")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,297 @@
+use super::{MerkleError, MerkleHasher, Sha256};
+use crate::{
+    codec::{CompactDecode, CompactEncode, DecodeError},
+    Mappable, MerkleRoot, StorageMutate,
+};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+fn leaf_hash<H: MerkleHasher>(data: &[u8]) -> MerkleRoot {
+    let mut hasher = H::default();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+fn node_hash<H: MerkleHasher>(left: &MerkleRoot, right: &MerkleRoot) -> MerkleRoot {
+    let mut hasher = H::default();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize()
+}
+
+/// Return the position of the root of the perfect subtree of `2^height` leaves starting from the
+/// leaf with the `index`, which should be aligned to the size of the subtree.
+///
+/// Positions follow the in-order traversal of the tree: leaves are at even positions and the root
+/// of the subtree is between its halves.
+fn position(index: u64, height: u32) -> u64 {
+    (index << 1) + (1 << height) - 1
+}
+
+/// The append-only binary Merkle tree over the ordered list of leaves, as defined by the RFC 6962:
+/// the leaf is hashed as `H(0x00 || data)`, the node as `H(0x01 || left || right)`, and the tree
+/// of `n` leaves is split into the perfect subtree of the largest power of two less than `n`
+/// leaves and the rest. The root of the empty tree is the hash of the empty input.
+///
+/// Roots of perfect subtrees never change after they are completed, so they are stored in the
+/// `Nodes` table under their position in the in-order traversal of the tree, and the tree keeps
+/// in memory only the roots of the largest perfect subtrees, which it is made of.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::{Array, BigEndian},
+///     merkle::{BinaryMerkleTree, Sha256},
+///     Mappable, MemoryKeyValueStore, MerkleRoot, StructuredStorage, Table, TableWithCodec,
+/// };
+///
+/// pub struct ReceiptsNodes;
+///
+/// impl Mappable for ReceiptsNodes {
+///     type Key = u64;
+///     type SetValue = MerkleRoot;
+///     type GetValue = MerkleRoot;
+/// }
+///
+/// impl TableWithCodec for ReceiptsNodes {
+///     type KeyCodec = BigEndian;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for ReceiptsNodes {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "ReceiptsNodes";
+/// }
+///
+/// let mut storage = StructuredStorage::new(MemoryKeyValueStore::new());
+/// let mut tree = BinaryMerkleTree::<ReceiptsNodes, _>::new(&mut storage);
+/// for receipt in [&b"first"[..], b"second", b"third"] {
+///     tree.push(receipt).unwrap();
+/// }
+/// let root = tree.root();
+///
+/// let proof = tree.prove(1).unwrap().unwrap();
+/// assert!(proof.verify::<Sha256>(&root, b"second"));
+/// assert!(!proof.verify::<Sha256>(&root, b"third"));
+///
+/// // The tree continues from the stored nodes.
+/// let mut tree = BinaryMerkleTree::<ReceiptsNodes, _>::load(&mut storage, 3).unwrap();
+/// assert_eq!(tree.root(), root);
+/// tree.push(b"fourth").unwrap();
+/// assert_ne!(tree.root(), root);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BinaryMerkleTree<Nodes, S, H = Sha256> {
+    storage: S,
+    leaves_count: u64,
+    /// The roots of the largest perfect subtrees, from the left to the right.
+    peaks: Vec<MerkleRoot>,
+    _marker: PhantomData<(Nodes, H)>,
+}
+
+impl<Nodes, S, H, E> BinaryMerkleTree<Nodes, S, H>
+where
+    Nodes: Mappable<Key = u64, SetValue = MerkleRoot, GetValue = MerkleRoot>,
+    S: StorageMutate<Nodes, Error = E>,
+    E: From<MerkleError>,
+    H: MerkleHasher,
+{
+    /// Create the empty tree over the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            leaves_count: 0,
+            peaks: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Load the tree of `leaves_count` leaves stored in the `storage`.
+    pub fn load(storage: S, leaves_count: u64) -> Result<Self, E> {
+        let mut peaks = Vec::new();
+        let mut index = 0;
+        for height in (0..u64::BITS).rev() {
+            if leaves_count & (1 << height) != 0 {
+                peaks.push(load::<Nodes, S>(&storage, position(index, height))?);
+                index += 1 << height;
+            }
+        }
+        Ok(Self {
+            storage,
+            leaves_count,
+            peaks,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Return the number of leaves in the tree.
+    pub fn leaves_count(&self) -> u64 {
+        self.leaves_count
+    }
+
+    /// Return the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Unwrap the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Append the leaf with the `data` to the tree.
+    pub fn push(&mut self, data: &[u8]) -> Result<(), E> {
+        let index = self.leaves_count;
+        let mut hash = leaf_hash::<H>(data);
+        self.storage.insert(&position(index, 0), &hash)?;
+
+        // Every trailing one bit of the index is the perfect subtree completed by the leaf.
+        let mut height = 0;
+        while index >> height & 1 == 1 {
+            let left = self
+                .peaks
+                .pop()
+                .expect("The tree has the peak for every set bit of the leaves count");
+            height += 1;
+            hash = node_hash::<H>(&left, &hash);
+            let start = (index >> height) << height;
+            self.storage.insert(&position(start, height), &hash)?;
+        }
+        self.peaks.push(hash);
+        self.leaves_count += 1;
+        Ok(())
+    }
+
+    /// Return the root of the tree.
+    pub fn root(&self) -> MerkleRoot {
+        let mut peaks = self.peaks.iter().rev();
+        match peaks.next() {
+            Some(last) => peaks.fold(*last, |root, peak| node_hash::<H>(peak, &root)),
+            None => H::hash(&[]),
+        }
+    }
+
+    /// Return the proof of inclusion of the leaf with the `index`, or `None` if there is no such
+    /// leaf.
+    pub fn prove(&self, index: u64) -> Result<Option<BinaryMerkleProof>, E> {
+        if index >= self.leaves_count {
+            return Ok(None);
+        }
+
+        // The path of the leaf in the tree of the leaves within `start..end`.
+        let mut path = Vec::new();
+        let (mut start, mut end) = (0, self.leaves_count);
+        while end - start > 1 {
+            let split = start + largest_power_of_two_below(end - start);
+            if index < split {
+                path.push(self.subtree_root(split, end)?);
+                end = split;
+            } else {
+                path.push(self.subtree_root(start, split)?);
+                start = split;
+            }
+        }
+        path.reverse();
+
+        Ok(Some(BinaryMerkleProof {
+            index,
+            leaves_count: self.leaves_count,
+            path,
+        }))
+    }
+
+    /// Return the root of the tree of the leaves within `start..end`, where the `start` is aligned
+    /// to the largest power of two not greater than the number of the leaves.
+    fn subtree_root(&self, start: u64, end: u64) -> Result<MerkleRoot, E> {
+        let len = end - start;
+        if len.is_power_of_two() {
+            return load::<Nodes, S>(&self.storage, position(start, len.trailing_zeros()));
+        }
+        let split = start + largest_power_of_two_below(len);
+        let left = self.subtree_root(start, split)?;
+        let right = self.subtree_root(split, end)?;
+        Ok(node_hash::<H>(&left, &right))
+    }
+}
+
+fn largest_power_of_two_below(value: u64) -> u64 {
+    1 << (u64::BITS - 1 - (value - 1).leading_zeros())
+}
+
+fn load<Nodes, S>(storage: &S, position: u64) -> Result<MerkleRoot, S::Error>
+where
+    Nodes: Mappable<Key = u64, SetValue = MerkleRoot, GetValue = MerkleRoot>,
+    S: StorageMutate<Nodes>,
+    S::Error: From<MerkleError>,
+{
+    let node = storage
+        .get(&position)?
+        .ok_or(MerkleError::MissingPosition(position))?;
+    Ok(node.into_owned())
+}
+
+/// The proof of inclusion of the leaf in the [`BinaryMerkleTree`], as defined by the RFC 9162.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct BinaryMerkleProof {
+    /// The index of the leaf.
+    pub index: u64,
+    /// The number of leaves in the tree.
+    pub leaves_count: u64,
+    /// The roots of the subtrees next to the way from the leaf to the root, starting from the leaf.
+    pub path: Vec<MerkleRoot>,
+}
+
+impl BinaryMerkleProof {
+    /// Return `true` if the proof proves that the leaf with the `data` is in the tree with the
+    /// `root` under the proven index. The `H` should be the same as used by the tree.
+    pub fn verify<H: MerkleHasher>(&self, root: &MerkleRoot, data: &[u8]) -> bool {
+        if self.index >= self.leaves_count {
+            return false;
+        }
+
+        let (mut index, mut last) = (self.index, self.leaves_count - 1);
+        let mut hash = leaf_hash::<H>(data);
+        for sibling in &self.path {
+            if last == 0 {
+                return false;
+            }
+            if index & 1 == 1 || index == last {
+                hash = node_hash::<H>(sibling, &hash);
+                // The right-most subtree of the level may be shallower than the tree.
+                while index & 1 == 0 && index != 0 {
+                    index >>= 1;
+                    last >>= 1;
+                }
+            } else {
+                hash = node_hash::<H>(&hash, sibling);
+            }
+            index >>= 1;
+            last >>= 1;
+        }
+        last == 0 && hash == *root
+    }
+}
+
+impl CompactEncode for BinaryMerkleProof {
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        self.index.encode_compact(buf);
+        self.leaves_count.encode_compact(buf);
+        self.path.encode_compact(buf);
+    }
+}
+
+impl CompactDecode for BinaryMerkleProof {
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            index: CompactDecode::decode_compact(bytes)?,
+            leaves_count: CompactDecode::decode_compact(bytes)?,
+            path: CompactDecode::decode_compact(bytes)?,
+        })
+    }
+}
@@ -0,0 +1,425 @@
+use super::{
+    sparse::{common_prefix_len, goes_right, load, NodesTable, MAX_DEPTH},
+    DigestOf, MerkleError, MerkleHasher, SparseDigest, SparseMerkleProof, SparseMerkleTable,
+    SparseNode, SparseTreeKind,
+};
+use crate::{
+    codec::{CompactDecode, CompactEncode, DecodeError, Encode},
+    MerkleRoot, StorageInspect,
+};
+use alloc::vec::Vec;
+
+/// The proof of inclusion or exclusion of several entries of the sparse Merkle tree at once,
+/// verified by [`verify_multiproof`].
+///
+/// The ways from the root to the positions of the entries share their upper nodes, so the proof
+/// contains every sibling only once, and the siblings that are the ways of other entries are
+/// recomputed by the verifier. The empty siblings, common in the lower levels of the tree, are
+/// marked in the bitmap instead of being listed.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::{Array, Compact},
+///     merkle::{
+///         verify_multiproof, Sha256, SparseMerkleStorage, SparseMerkleTable, SparseNode,
+///         ValueHash,
+///     },
+///     Mappable, MemoryKeyValueStore, MerkleRoot, StorageAsMut, StructuredStorage, Table,
+///     TableWithCodec,
+/// };
+///
+/// /// The storage slots of contracts, keyed by the contract id and the slot.
+/// pub struct ContractsState;
+///
+/// impl Mappable for ContractsState {
+///     type Key = ([u8; 32], [u8; 32]);
+///     type SetValue = [u8; 32];
+///     type GetValue = [u8; 32];
+/// }
+///
+/// impl TableWithCodec for ContractsState {
+///     type KeyCodec = Compact;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for ContractsState {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "ContractsState";
+/// }
+///
+/// impl SparseMerkleTable for ContractsState {
+///     type Kind = ValueHash;
+///     type Nodes = StateNodes;
+///     type Roots = StateRoots;
+///
+///     fn tree_key(key: &Self::Key) -> [u8; 32] {
+///         key.0
+///     }
+/// }
+///
+/// pub struct StateNodes;
+///
+/// impl Mappable for StateNodes {
+///     type Key = MerkleRoot;
+///     type SetValue = SparseNode;
+///     type GetValue = SparseNode;
+/// }
+///
+/// impl TableWithCodec for StateNodes {
+///     type KeyCodec = Array;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for StateNodes {
+///     const COLUMN: u32 = 1;
+///     const NAME: &'static str = "StateNodes";
+/// }
+///
+/// pub struct StateRoots;
+///
+/// impl Mappable for StateRoots {
+///     type Key = [u8; 32];
+///     type SetValue = MerkleRoot;
+///     type GetValue = MerkleRoot;
+/// }
+///
+/// impl TableWithCodec for StateRoots {
+///     type KeyCodec = Array;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for StateRoots {
+///     const COLUMN: u32 = 2;
+///     const NAME: &'static str = "StateRoots";
+/// }
+///
+/// let mut storage: SparseMerkleStorage<_> =
+///     SparseMerkleStorage::new(StructuredStorage::new(MemoryKeyValueStore::new()));
+/// let contract = [1; 32];
+/// for slot in 0..16 {
+///     storage.storage::<ContractsState>().insert(&(contract, [slot; 32]), &[slot; 32]).unwrap();
+/// }
+/// let root = storage.storage::<ContractsState>().root(&contract).unwrap();
+///
+/// let slots = [(contract, [3; 32]), (contract, [7; 32]), (contract, [100; 32])];
+/// let proof = storage.storage::<ContractsState>().prove_many(&contract, &slots).unwrap();
+/// let entries = [
+///     (&slots[0], Some(&[3; 32])),
+///     (&slots[1], Some(&[7; 32])),
+///     (&slots[2], None),
+/// ];
+/// assert!(verify_multiproof::<ContractsState, Sha256>(&root, &entries, &proof));
+///
+/// let entries = [(&slots[0], Some(&[3; 32])), (&slots[1], None), (&slots[2], None)];
+/// assert!(!verify_multiproof::<ContractsState, Sha256>(&root, &entries, &proof));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SparseMerkleMultiproof<D: SparseDigest = MerkleRoot> {
+    /// The depths of the subtrees where the ways to the positions of the entries end, from the
+    /// left to the right.
+    pub depths: Vec<u16>,
+    /// The path and the data of the leaf of every subtree where the ways end, or `None` if the
+    /// subtree is empty.
+    pub leaves: Vec<Option<(MerkleRoot, D::Leaf)>>,
+    /// The digests of the non-empty siblings of the ways, in the order of the depth-first
+    /// traversal from the left to the right.
+    pub siblings: Vec<D>,
+    /// The bitmap of all siblings in the order of the traversal, with the bit set for the empty
+    /// ones, starting from the lowest bit of the first byte.
+    pub empty: Vec<u8>,
+}
+
+impl<D> CompactEncode for SparseMerkleMultiproof<D>
+where
+    D: SparseDigest + CompactEncode,
+    D::Leaf: CompactEncode,
+{
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        self.depths.encode_compact(buf);
+        self.leaves.encode_compact(buf);
+        self.siblings.encode_compact(buf);
+        self.empty.encode_compact(buf);
+    }
+}
+
+impl<D> CompactDecode for SparseMerkleMultiproof<D>
+where
+    D: SparseDigest + CompactDecode,
+    D::Leaf: CompactDecode,
+{
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            depths: CompactDecode::decode_compact(bytes)?,
+            leaves: CompactDecode::decode_compact(bytes)?,
+            siblings: CompactDecode::decode_compact(bytes)?,
+            empty: CompactDecode::decode_compact(bytes)?,
+        })
+    }
+}
+
+impl<D: SparseDigest> SparseMerkleMultiproof<D> {
+    fn push_sibling(&mut self, slot: &mut usize, sibling: D) {
+        if slot.is_multiple_of(8) {
+            self.empty.push(0);
+        }
+        if sibling == D::EMPTY {
+            self.empty[*slot / 8] |= 1 << (*slot % 8);
+        } else {
+            self.siblings.push(sibling);
+        }
+        *slot += 1;
+    }
+}
+
+/// The proof of the single entry is the multiproof of that entry.
+impl<D: SparseDigest> From<SparseMerkleProof<D>> for SparseMerkleMultiproof<D> {
+    fn from(proof: SparseMerkleProof<D>) -> Self {
+        let depth = u16::try_from(proof.siblings.len()).unwrap_or(u16::MAX);
+        let mut multiproof = Self {
+            depths: alloc::vec![depth],
+            leaves: alloc::vec![proof.leaf],
+            siblings: Vec::new(),
+            empty: Vec::new(),
+        };
+        let mut slot = 0;
+        for sibling in proof.siblings {
+            multiproof.push_sibling(&mut slot, sibling);
+        }
+        multiproof
+    }
+}
+
+/// The digests and the nodes of the tree recomputed from the proof.
+pub(crate) type RecomputedNodes<D> = Vec<(D, SparseNode<D>)>;
+
+/// Return the multiproof of the positions at the sorted and deduplicated `paths` in the tree with
+/// the `root`.
+pub(crate) fn prove<D, Nodes, S>(
+    storage: &S,
+    root: D,
+    paths: &[MerkleRoot],
+) -> Result<SparseMerkleMultiproof<D>, S::Error>
+where
+    D: SparseDigest,
+    Nodes: NodesTable<D>,
+    S: StorageInspect<Nodes>,
+    S::Error: From<MerkleError>,
+{
+    let mut proof = SparseMerkleMultiproof {
+        depths: Vec::new(),
+        leaves: Vec::new(),
+        siblings: Vec::new(),
+        empty: Vec::new(),
+    };
+    if !paths.is_empty() {
+        prove_subtree::<D, Nodes, S>(storage, root, 0, paths, &mut proof, &mut 0)?;
+    }
+    Ok(proof)
+}
+
+fn prove_subtree<D, Nodes, S>(
+    storage: &S,
+    digest: D,
+    depth: u16,
+    paths: &[MerkleRoot],
+    proof: &mut SparseMerkleMultiproof<D>,
+    slot: &mut usize,
+) -> Result<(), S::Error>
+where
+    D: SparseDigest,
+    Nodes: NodesTable<D>,
+    S: StorageInspect<Nodes>,
+    S::Error: From<MerkleError>,
+{
+    if digest == D::EMPTY {
+        proof.depths.push(depth);
+        proof.leaves.push(None);
+        return Ok(());
+    }
+    let (left, right) = match load::<D, Nodes, S>(storage, &digest)? {
+        SparseNode::Leaf { path, data } => {
+            proof.depths.push(depth);
+            proof.leaves.push(Some((path, data)));
+            return Ok(());
+        }
+        SparseNode::Branch { .. } if depth as usize == MAX_DEPTH => {
+            return Err(MerkleError::TooDeep.into());
+        }
+        SparseNode::Branch { left, right } => (left, right),
+    };
+
+    let split = paths.partition_point(|path| !goes_right(path, depth as usize));
+    let (left_paths, right_paths) = paths.split_at(split);
+    if left_paths.is_empty() {
+        proof.push_sibling(slot, left);
+    } else if right_paths.is_empty() {
+        proof.push_sibling(slot, right);
+    }
+    if !left_paths.is_empty() {
+        prove_subtree::<D, Nodes, S>(storage, left, depth + 1, left_paths, proof, slot)?;
+    }
+    if !right_paths.is_empty() {
+        prove_subtree::<D, Nodes, S>(storage, right, depth + 1, right_paths, proof, slot)?;
+    }
+    Ok(())
+}
+
+/// Return `true` if the `proof` proves that every entry with the key has the value in the tree
+/// with the `root`, or that there is no such entry if the value is `None`.
+///
+/// The `H` should be the same as used by the [`SparseMerkleStorage`](super::SparseMerkleStorage)
+/// that produced the root.
+pub fn verify_multiproof<Type, H>(
+    root: &DigestOf<Type>,
+    entries: &[(&Type::Key, Option<&Type::SetValue>)],
+    proof: &SparseMerkleMultiproof<DigestOf<Type>>,
+) -> bool
+where
+    Type: SparseMerkleTable,
+    H: MerkleHasher,
+{
+    let leaves = entries
+        .iter()
+        .map(|(key, value)| {
+            let path = H::hash(&Type::KeyCodec::encode(key));
+            (
+                path,
+                value.map(<Type::Kind as SparseTreeKind<Type>>::leaf::<H>),
+            )
+        })
+        .collect();
+    proven_nodes::<_, H>(root, leaves, proof).is_some()
+}
+
+/// Return the digests and the nodes of the tree with the `root` that are proven by the `proof`
+/// together with the `leaves` of the entries, or `None` if the proof is invalid.
+pub(crate) fn proven_nodes<D: SparseDigest, H: MerkleHasher>(
+    root: &D,
+    leaves: Vec<(MerkleRoot, Option<D::Leaf>)>,
+    proof: &SparseMerkleMultiproof<D>,
+) -> Option<RecomputedNodes<D>> {
+    if leaves.is_empty() {
+        let empty = proof.depths.is_empty()
+            && proof.leaves.is_empty()
+            && proof.siblings.is_empty()
+            && proof.empty.is_empty();
+        return empty.then(Vec::new);
+    }
+    let (digest, nodes) = recompute::<D, H>(leaves, proof)?;
+    (digest == *root).then_some(nodes)
+}
+
+/// Return the digest of the root of the tree recomputed from the `proof` and the non-empty
+/// `leaves` of the entries, together with the digests and the nodes proven on the way, or `None`
+/// if the proof is malformed or inconsistent with the entries.
+pub(crate) fn recompute<D: SparseDigest, H: MerkleHasher>(
+    mut leaves: Vec<(MerkleRoot, Option<D::Leaf>)>,
+    proof: &SparseMerkleMultiproof<D>,
+) -> Option<(D, RecomputedNodes<D>)> {
+    leaves.sort_unstable_by_key(|(path, _)| *path);
+    let mut cursor = Cursor {
+        proof,
+        terminal: 0,
+        sibling: 0,
+        slot: 0,
+        nodes: Vec::new(),
+    };
+    let digest = cursor.subtree::<H>(0, &leaves)?;
+    let unused_bits = match cursor.slot % 8 {
+        0 => 0,
+        used => proof.empty.last().map_or(0, |byte| byte >> used),
+    };
+    let valid = cursor.terminal == proof.depths.len()
+        && cursor.terminal == proof.leaves.len()
+        && cursor.sibling == proof.siblings.len()
+        && proof.empty.len() == cursor.slot.div_ceil(8)
+        && unused_bits == 0;
+    valid.then_some((digest, cursor.nodes))
+}
+
+/// The position of the verifier in the multiproof.
+struct Cursor<'a, D: SparseDigest> {
+    proof: &'a SparseMerkleMultiproof<D>,
+    terminal: usize,
+    sibling: usize,
+    slot: usize,
+    /// The digests and the nodes of the tree recomputed so far.
+    nodes: RecomputedNodes<D>,
+}
+
+impl<D: SparseDigest> Cursor<'_, D> {
+    fn next_sibling(&mut self) -> Option<D> {
+        let empty = self.proof.empty.get(self.slot / 8)? >> (self.slot % 8) & 1 == 1;
+        self.slot += 1;
+        if empty {
+            return Some(D::EMPTY);
+        }
+        let sibling = self.proof.siblings.get(self.sibling)?;
+        self.sibling += 1;
+        Some(*sibling)
+    }
+
+    /// Return the digest of the subtree at the `depth` with the sorted `leaves` of the proven
+    /// entries below it, or `None` if the proof is invalid.
+    fn subtree<H: MerkleHasher>(
+        &mut self,
+        depth: usize,
+        leaves: &[(MerkleRoot, Option<D::Leaf>)],
+    ) -> Option<D> {
+        let terminal_depth = *self.proof.depths.get(self.terminal)? as usize;
+        if terminal_depth == depth {
+            let leaf = *self.proof.leaves.get(self.terminal)?;
+            self.terminal += 1;
+            let digest = terminal::<D, H>(depth, leaf, leaves)?;
+            if let Some((path, data)) = leaf {
+                self.nodes.push((digest, SparseNode::Leaf { path, data }));
+            }
+            return Some(digest);
+        }
+        if terminal_depth < depth || depth >= MAX_DEPTH {
+            return None;
+        }
+
+        let split = leaves.partition_point(|(path, _)| !goes_right(path, depth));
+        let (left, right) = leaves.split_at(split);
+        let (left, right) = if left.is_empty() {
+            let sibling = self.next_sibling()?;
+            (sibling, self.subtree::<H>(depth + 1, right)?)
+        } else if right.is_empty() {
+            let sibling = self.next_sibling()?;
+            (self.subtree::<H>(depth + 1, left)?, sibling)
+        } else {
+            let left = self.subtree::<H>(depth + 1, left)?;
+            (left, self.subtree::<H>(depth + 1, right)?)
+        };
+        let digest = D::branch::<H>(&left, &right).ok()?;
+        self.nodes
+            .push((digest, SparseNode::Branch { left, right }));
+        Some(digest)
+    }
+}
+
+/// Return the digest of the subtree at the `depth` that is empty or consists of the `leaf`, if
+/// it is consistent with the `leaves` of the proven entries below it.
+fn terminal<D: SparseDigest, H: MerkleHasher>(
+    depth: usize,
+    leaf: Option<(MerkleRoot, D::Leaf)>,
+    leaves: &[(MerkleRoot, Option<D::Leaf>)],
+) -> Option<D> {
+    let Some((leaf_path, leaf_data)) = leaf else {
+        return leaves
+            .iter()
+            .all(|(_, data)| data.is_none())
+            .then_some(D::EMPTY);
+    };
+    leaves
+        .iter()
+        .all(|(path, data)| match data {
+            Some(data) => *path == leaf_path && *data == leaf_data,
+            // The leaf of another entry should be on the way to the position of the key.
+            None => *path != leaf_path && common_prefix_len(path, &leaf_path) >= depth,
+        })
+        .then(|| D::leaf::<H>(&leaf_path, &leaf_data))
+}
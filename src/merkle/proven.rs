@@ -0,0 +1,302 @@
+use super::{
+    multiproof::proven_nodes,
+    sparse::{update, NodesTable},
+    DigestOf, MerkleError, MerkleHasher, Sha256, SparseDigest, SparseMerkleMultiproof,
+    SparseMerkleProof, SparseMerkleTable, SparseNode, SparseTreeKind, TreeWitness,
+};
+use crate::{
+    borrow,
+    codec::{Decode, Encode},
+    MerkleRoot, StorageError, StorageInspect, StorageMutate,
+};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeMap,
+    vec::Vec,
+};
+use core::marker::PhantomData;
+
+/// The storage of the entries of one sparse Merkle tree built from nothing but the root of the
+/// tree and the proofs of the entries, for re-executing blocks without the database.
+///
+/// Every entry read or written should be covered by the added proofs, otherwise the storage fails
+/// with the [`MerkleError::Unproven`]. Writes update the tree over the proven nodes, so the
+/// [`root`](Self::root) is the root of the tree after the writes. The removal that leaves the
+/// neighbouring subtree with one entry also needs the node of that subtree, which is covered by the
+/// proof of any entry in it.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::{Array, Compact},
+///     merkle::{
+///         MerkleError, ProvenStorage, SparseMerkleStorage, SparseMerkleTable, SparseNode,
+///         ValueHash,
+///     },
+///     Mappable, MemoryKeyValueStore, MerkleRoot, StorageAsMut, StorageError, StructuredStorage,
+///     Table, TableWithCodec,
+/// };
+///
+/// /// The storage slots of contracts, keyed by the contract id and the slot.
+/// pub struct ContractsState;
+///
+/// impl Mappable for ContractsState {
+///     type Key = ([u8; 32], [u8; 32]);
+///     type SetValue = [u8; 32];
+///     type GetValue = [u8; 32];
+/// }
+///
+/// impl TableWithCodec for ContractsState {
+///     type KeyCodec = Compact;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for ContractsState {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "ContractsState";
+/// }
+///
+/// impl SparseMerkleTable for ContractsState {
+///     type Kind = ValueHash;
+///     type Nodes = StateNodes;
+///     type Roots = StateRoots;
+///
+///     fn tree_key(key: &Self::Key) -> [u8; 32] {
+///         key.0
+///     }
+/// }
+///
+/// pub struct StateNodes;
+///
+/// impl Mappable for StateNodes {
+///     type Key = MerkleRoot;
+///     type SetValue = SparseNode;
+///     type GetValue = SparseNode;
+/// }
+///
+/// impl TableWithCodec for StateNodes {
+///     type KeyCodec = Array;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for StateNodes {
+///     const COLUMN: u32 = 1;
+///     const NAME: &'static str = "StateNodes";
+/// }
+///
+/// pub struct StateRoots;
+///
+/// impl Mappable for StateRoots {
+///     type Key = [u8; 32];
+///     type SetValue = MerkleRoot;
+///     type GetValue = MerkleRoot;
+/// }
+///
+/// impl TableWithCodec for StateRoots {
+///     type KeyCodec = Array;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for StateRoots {
+///     const COLUMN: u32 = 2;
+///     const NAME: &'static str = "StateRoots";
+/// }
+///
+/// let mut storage: SparseMerkleStorage<_> =
+///     SparseMerkleStorage::new(StructuredStorage::new(MemoryKeyValueStore::new()));
+/// let contract = [1; 32];
+/// for slot in 0..8 {
+///     storage.storage::<ContractsState>().insert(&(contract, [slot; 32]), &[slot; 32]).unwrap();
+/// }
+/// let root = storage.storage::<ContractsState>().root(&contract).unwrap();
+///
+/// // The witness of the block covers the slot it reads and the slot it creates.
+/// let (read, created) = ((contract, [3; 32]), (contract, [42; 32]));
+/// let proof =
+///     storage.storage::<ContractsState>().prove_many(&contract, &[read, created]).unwrap();
+///
+/// let mut proven = ProvenStorage::<ContractsState>::new(root);
+/// proven.add_multiproof(&[(&read, Some(&[3; 32])), (&created, None)], &proof).unwrap();
+/// let value = proven.storage::<ContractsState>().get(&read).unwrap().unwrap().into_owned();
+/// assert_eq!(value, [3; 32]);
+/// assert!(matches!(
+///     proven.storage::<ContractsState>().get(&(contract, [4; 32])),
+///     Err(StorageError::Merkle(MerkleError::Unproven(_)))
+/// ));
+///
+/// // The root after the writes is the same as the root of the full storage.
+/// proven.storage::<ContractsState>().insert(&created, &[42; 32]).unwrap();
+/// storage.storage::<ContractsState>().insert(&created, &[42; 32]).unwrap();
+/// assert_eq!(Ok(proven.root()), storage.storage::<ContractsState>().root(&contract));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProvenStorage<Type: SparseMerkleTable, H = Sha256> {
+    initial_root: DigestOf<Type>,
+    root: DigestOf<Type>,
+    nodes: ProvenNodes<DigestOf<Type>>,
+    /// The encoded values of the covered entries, keyed by the path of the entry.
+    values: BTreeMap<MerkleRoot, Option<Vec<u8>>>,
+    _hasher: PhantomData<H>,
+}
+
+impl<Type: SparseMerkleTable, H: MerkleHasher> ProvenStorage<Type, H> {
+    /// Create the storage of the tree with the `root` without covered entries.
+    pub fn new(root: DigestOf<Type>) -> Self {
+        Self {
+            initial_root: root,
+            root,
+            nodes: ProvenNodes(BTreeMap::new()),
+            values: BTreeMap::new(),
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Create the storage of the tree covered by the `witness`.
+    pub fn from_witness(witness: &TreeWitness<Type>) -> Result<Self, StorageError>
+    where
+        Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    {
+        let entries: Vec<_> = witness
+            .entries
+            .iter()
+            .map(|(key, value)| (key, value.as_ref().map(borrow::<Type::SetValue>)))
+            .collect();
+        let mut storage = Self::new(witness.root);
+        storage.add_multiproof(&entries, &witness.proof)?;
+        Ok(storage)
+    }
+
+    /// Return the digest of the root of the tree before the writes.
+    pub fn initial_root(&self) -> DigestOf<Type> {
+        self.initial_root
+    }
+
+    /// Return the digest of the root of the tree after the writes.
+    pub fn root(&self) -> DigestOf<Type> {
+        self.root
+    }
+
+    /// Cover the entry with the `key` that has the `value`, or no value if it is `None`, by the
+    /// `proof` against the initial root.
+    pub fn add_proof(
+        &mut self,
+        key: &Type::Key,
+        value: Option<&Type::SetValue>,
+        proof: &SparseMerkleProof<DigestOf<Type>>,
+    ) -> Result<(), StorageError> {
+        self.add_multiproof(&[(key, value)], &proof.clone().into())
+    }
+
+    /// Cover the `entries` by the `proof` against the initial root, as verified by the
+    /// [`verify_multiproof`](super::verify_multiproof). Entries that are already covered keep
+    /// their current values.
+    pub fn add_multiproof(
+        &mut self,
+        entries: &[(&Type::Key, Option<&Type::SetValue>)],
+        proof: &SparseMerkleMultiproof<DigestOf<Type>>,
+    ) -> Result<(), StorageError> {
+        let mut leaves = Vec::with_capacity(entries.len());
+        let mut values = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            let path = H::hash(&Type::KeyCodec::encode(key));
+            let leaf = value.map(<Type::Kind as SparseTreeKind<Type>>::leaf::<H>);
+            let value = value.map(|value| Type::ValueCodec::encode(value).into_owned());
+            leaves.push((path, leaf));
+            values.push((path, value));
+        }
+
+        let nodes = proven_nodes::<_, H>(&self.initial_root, leaves, proof)
+            .ok_or(MerkleError::InvalidProof)?;
+        for (digest, node) in nodes {
+            self.nodes.0.insert(SparseDigest::hash(&digest), node);
+        }
+        for (path, value) in values {
+            self.values.entry(path).or_insert(value);
+        }
+        Ok(())
+    }
+
+    /// Return the encoded value of the entry with the `key` and its path, if the entry is covered.
+    fn covered(&self, key: &Type::Key) -> Result<(MerkleRoot, Option<&[u8]>), StorageError> {
+        let path = H::hash(&Type::KeyCodec::encode(key));
+        match self.values.get(&path) {
+            Some(value) => Ok((path, value.as_deref())),
+            None => Err(MerkleError::Unproven(path).into()),
+        }
+    }
+}
+
+impl<Type: SparseMerkleTable, H: MerkleHasher> StorageInspect<Type> for ProvenStorage<Type, H> {
+    type Error = StorageError;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        match self.covered(key)? {
+            (_, Some(value)) => Ok(Some(Cow::Owned(Type::ValueCodec::decode(value)?))),
+            (_, None) => Ok(None),
+        }
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        Ok(self.covered(key)?.1.is_some())
+    }
+}
+
+impl<Type: SparseMerkleTable, H: MerkleHasher> StorageMutate<Type> for ProvenStorage<Type, H> {
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        let (path, _) = self.covered(key)?;
+        let leaf = <Type::Kind as SparseTreeKind<Type>>::leaf::<H>(value);
+        self.root = update::<H, _, Type::Nodes, _>(&mut self.nodes, self.root, &path, Some(leaf))?;
+        let value = Type::ValueCodec::encode(value).into_owned();
+        match self.values.insert(path, Some(value)).flatten() {
+            Some(previous) => Ok(Some(Type::ValueCodec::decode(&previous)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        let (path, value) = self.covered(key)?;
+        if value.is_none() {
+            return Ok(None);
+        }
+        self.root = update::<H, _, Type::Nodes, _>(&mut self.nodes, self.root, &path, None)?;
+        match self.values.insert(path, None).flatten() {
+            Some(previous) => Ok(Some(Type::ValueCodec::decode(&previous)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The proven nodes of the tree, keyed by their hashes.
+#[derive(Debug, Clone)]
+struct ProvenNodes<D: SparseDigest>(BTreeMap<MerkleRoot, SparseNode<D>>);
+
+impl<D: SparseDigest, Nodes: NodesTable<D>> StorageInspect<Nodes> for ProvenNodes<D> {
+    type Error = StorageError;
+
+    fn get(&self, key: &MerkleRoot) -> Result<Option<Cow<'_, SparseNode<D>>>, Self::Error> {
+        Ok(self.0.get(key).map(Cow::Borrowed))
+    }
+
+    fn contains_key(&self, key: &MerkleRoot) -> Result<bool, Self::Error> {
+        Ok(self.0.contains_key(key))
+    }
+}
+
+impl<D: SparseDigest, Nodes: NodesTable<D>> StorageMutate<Nodes> for ProvenNodes<D> {
+    fn insert(
+        &mut self,
+        key: &MerkleRoot,
+        value: &SparseNode<D>,
+    ) -> Result<Option<SparseNode<D>>, Self::Error> {
+        Ok(self.0.insert(*key, *value))
+    }
+
+    fn remove(&mut self, key: &MerkleRoot) -> Result<Option<SparseNode<D>>, Self::Error> {
+        Ok(self.0.remove(key))
+    }
+}
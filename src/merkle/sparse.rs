@@ -0,0 +1,817 @@
+use super::{multiproof, MerkleError, MerkleHasher, Sha256, SparseMerkleMultiproof};
+use crate::{
+    codec::{CompactDecode, CompactEncode, DecodeError, Encode},
+    BoxedIter, IterDirection, KVItem, Mappable, MerkleMultiproofStorage, MerkleProofStorage,
+    MerkleRoot, MerkleRootStorage, StorageBatchMutate, StorageInspect, StorageIterate,
+    StorageMutate, StorageWrite, TableWithCodec,
+};
+use alloc::{borrow::Cow, vec::Vec};
+use core::{fmt::Debug, hash::Hash, marker::PhantomData, ops::Bound};
+
+pub(crate) const LEAF_PREFIX: u8 = 0;
+pub(crate) const BRANCH_PREFIX: u8 = 1;
+
+/// The summary of the subtree that its parent commits to: the hash of the subtree and anything
+/// else the tree commits to, like the sum of the values in the subtree.
+pub trait SparseDigest: Copy + Eq + Hash + Debug {
+    /// What the leaf commits to besides its path.
+    type Leaf: Copy + Eq + Hash + Debug;
+
+    /// The digest of the empty subtree, which is also the root of the empty tree.
+    const EMPTY: Self;
+
+    /// Return the hash of the subtree, under which its root node is stored.
+    fn hash(&self) -> MerkleRoot;
+
+    /// Return the digest of the leaf with the `path` and the `data`.
+    fn leaf<H: MerkleHasher>(path: &MerkleRoot, data: &Self::Leaf) -> Self;
+
+    /// Return the digest of the branch with the `left` and the `right` subtrees.
+    fn branch<H: MerkleHasher>(left: &Self, right: &Self) -> Result<Self, MerkleError>;
+}
+
+/// The hash is the digest of trees that commit only to the hashes of the values.
+impl SparseDigest for MerkleRoot {
+    type Leaf = MerkleRoot;
+
+    const EMPTY: Self = [0; 32];
+
+    fn hash(&self) -> MerkleRoot {
+        *self
+    }
+
+    fn leaf<H: MerkleHasher>(path: &MerkleRoot, data: &Self::Leaf) -> Self {
+        let mut hasher = H::default();
+        hasher.update(&[LEAF_PREFIX]);
+        hasher.update(path);
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    fn branch<H: MerkleHasher>(left: &Self, right: &Self) -> Result<Self, MerkleError> {
+        let mut hasher = H::default();
+        hasher.update(&[BRANCH_PREFIX]);
+        hasher.update(left);
+        hasher.update(right);
+        Ok(hasher.finalize())
+    }
+}
+
+/// What the leaves of sparse Merkle trees over the `Type` commit to.
+pub trait SparseTreeKind<Type: Mappable + ?Sized> {
+    /// The digest of subtrees.
+    type Digest: SparseDigest;
+
+    /// Return what the leaf of the entry with the `value` commits to.
+    fn leaf<H: MerkleHasher>(value: &Type::SetValue) -> <Self::Digest as SparseDigest>::Leaf;
+}
+
+/// The kind of sparse Merkle trees whose leaves commit to the hashes of the encoded values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueHash;
+
+impl<Type: TableWithCodec + ?Sized> SparseTreeKind<Type> for ValueHash {
+    type Digest = MerkleRoot;
+
+    fn leaf<H: MerkleHasher>(value: &Type::SetValue) -> MerkleRoot {
+        H::hash(&Type::ValueCodec::encode(value))
+    }
+}
+
+/// The digest of subtrees of the trees over the `Type`.
+pub type DigestOf<Type> = <<Type as SparseMerkleTable>::Kind as SparseTreeKind<Type>>::Digest;
+
+/// The node of the sparse Merkle tree, stored under its hash.
+///
+/// The subtree with exactly one entry is represented by the leaf of that entry, so branches always
+/// have at least two entries below them and the tree is at most as deep as needed to tell its keys
+/// apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SparseNode<D: SparseDigest = MerkleRoot> {
+    /// The only entry of the subtree: the hash of its encoded key and what the leaf commits to.
+    Leaf { path: MerkleRoot, data: D::Leaf },
+    /// The subtree with at least two entries: the digests of its left and right halves.
+    Branch { left: D, right: D },
+}
+
+impl<D: SparseDigest> SparseNode<D> {
+    /// Return the digest of the node.
+    pub fn digest<H: MerkleHasher>(&self) -> Result<D, MerkleError> {
+        match self {
+            Self::Leaf { path, data } => Ok(D::leaf::<H>(path, data)),
+            Self::Branch { left, right } => D::branch::<H>(left, right),
+        }
+    }
+}
+
+impl<D> CompactEncode for SparseNode<D>
+where
+    D: SparseDigest + CompactEncode,
+    D::Leaf: CompactEncode,
+{
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Leaf { path, data } => {
+                buf.push(LEAF_PREFIX);
+                path.encode_compact(buf);
+                data.encode_compact(buf);
+            }
+            Self::Branch { left, right } => {
+                buf.push(BRANCH_PREFIX);
+                left.encode_compact(buf);
+                right.encode_compact(buf);
+            }
+        }
+    }
+}
+
+impl<D> CompactDecode for SparseNode<D>
+where
+    D: SparseDigest + CompactDecode,
+    D::Leaf: CompactDecode,
+{
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        match u8::decode_compact(bytes)? {
+            LEAF_PREFIX => Ok(Self::Leaf {
+                path: CompactDecode::decode_compact(bytes)?,
+                data: CompactDecode::decode_compact(bytes)?,
+            }),
+            BRANCH_PREFIX => Ok(Self::Branch {
+                left: CompactDecode::decode_compact(bytes)?,
+                right: CompactDecode::decode_compact(bytes)?,
+            }),
+            _ => Err(DecodeError::InvalidValue),
+        }
+    }
+}
+
+/// The [`Mappable`] whose entries are committed to by sparse Merkle trees, one tree per the tree
+/// key derived from the key of the entry.
+///
+/// The position of the entry in the tree is the hash of its encoded key, and the leaf commits to
+/// the value as defined by the [`SparseTreeKind`], so the root depends only on the content of the
+/// tree.
+pub trait SparseMerkleTable: TableWithCodec {
+    /// What the leaves commit to: [`ValueHash`] for the hashes of the values or
+    /// [`Sum`](super::Sum) for the values and their sums.
+    type Kind: SparseTreeKind<Self>;
+    /// The table of the nodes of all trees, keyed by the hash of the node. The same table may be
+    /// shared by several [`SparseMerkleTable`]s of the same kind.
+    type Nodes: Mappable<
+        Key = MerkleRoot,
+        SetValue = SparseNode<<Self::Kind as SparseTreeKind<Self>>::Digest>,
+        GetValue = SparseNode<<Self::Kind as SparseTreeKind<Self>>::Digest>,
+    >;
+    /// The table of the digests of the roots of non-empty trees, keyed by the tree key.
+    type Roots: Mappable<
+        SetValue = <Self::Kind as SparseTreeKind<Self>>::Digest,
+        GetValue = <Self::Kind as SparseTreeKind<Self>>::Digest,
+    >;
+
+    /// Return the key of the tree that the entry with the `key` belongs to.
+    fn tree_key(key: &Self::Key) -> <Self::Roots as Mappable>::Key;
+}
+
+/// The proof of inclusion or exclusion of the entry in the sparse Merkle tree, verified by
+/// [`verify`].
+///
+/// It contains the siblings of the nodes on the way from the root to the position of the entry,
+/// which ends either in the empty subtree or in the leaf. The entry is included if the leaf is the
+/// leaf of the entry, and it is excluded if the way ends in the empty subtree or in the leaf of
+/// another entry.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SparseMerkleProof<D: SparseDigest = MerkleRoot> {
+    /// The digests of the siblings of the nodes on the way from the root, starting from the top.
+    pub siblings: Vec<D>,
+    /// The path and the data of the leaf where the way ends, if any.
+    pub leaf: Option<(MerkleRoot, D::Leaf)>,
+}
+
+impl<D> CompactEncode for SparseMerkleProof<D>
+where
+    D: SparseDigest + CompactEncode,
+    D::Leaf: CompactEncode,
+{
+    fn encode_compact(&self, buf: &mut Vec<u8>) {
+        self.siblings.encode_compact(buf);
+        self.leaf.encode_compact(buf);
+    }
+}
+
+impl<D> CompactDecode for SparseMerkleProof<D>
+where
+    D: SparseDigest + CompactDecode,
+    D::Leaf: CompactDecode,
+{
+    fn decode_compact(bytes: &mut &[u8]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            siblings: CompactDecode::decode_compact(bytes)?,
+            leaf: CompactDecode::decode_compact(bytes)?,
+        })
+    }
+}
+
+/// Return `true` if the `proof` proves that the entry with the `key` has the `value` in the tree
+/// with the `root`, or that there is no such entry if the `value` is `None`.
+///
+/// The `H` should be the same as used by the [`SparseMerkleStorage`] that produced the root.
+pub fn verify<Type, H>(
+    root: &DigestOf<Type>,
+    key: &Type::Key,
+    value: Option<&Type::SetValue>,
+    proof: &SparseMerkleProof<DigestOf<Type>>,
+) -> bool
+where
+    Type: SparseMerkleTable,
+    H: MerkleHasher,
+{
+    let path = H::hash(&Type::KeyCodec::encode(key));
+    let depth = proof.siblings.len();
+    if depth > path.len() * 8 {
+        return false;
+    }
+
+    let mut digest = match (proof.leaf, value) {
+        (Some((leaf_path, data)), Some(value)) => {
+            if leaf_path != path || data != Type::Kind::leaf::<H>(value) {
+                return false;
+            }
+            DigestOf::<Type>::leaf::<H>(&leaf_path, &data)
+        }
+        (Some((leaf_path, data)), None) => {
+            // The leaf of another entry should be on the way to the position of the key.
+            if leaf_path == path || common_prefix_len(&path, &leaf_path) < depth {
+                return false;
+            }
+            DigestOf::<Type>::leaf::<H>(&leaf_path, &data)
+        }
+        (None, Some(_)) => return false,
+        (None, None) => DigestOf::<Type>::EMPTY,
+    };
+    for (depth, sibling) in proof.siblings.iter().enumerate().rev() {
+        let branch = if goes_right(&path, depth) {
+            DigestOf::<Type>::branch::<H>(sibling, &digest)
+        } else {
+            DigestOf::<Type>::branch::<H>(&digest, sibling)
+        };
+        match branch {
+            Ok(branch) => digest = branch,
+            Err(_) => return false,
+        }
+    }
+    digest == *root
+}
+
+/// The wrapper around the storage that maintains sparse Merkle trees over every
+/// [`SparseMerkleTable`] and implements [`MerkleRootStorage`] for them with the `H` hash function.
+/// Roots are updated incrementally on every `insert` and `remove`, and the nodes are stored in the
+/// [`SparseMerkleTable::Nodes`] table of the wrapped storage.
+///
+/// The error of the wrapped storage should be convertible from the [`MerkleError`] to report
+/// the corrupted tree.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::{Array, Compact},
+///     merkle::{verify, Sha256, SparseMerkleStorage, SparseMerkleTable, SparseNode, ValueHash},
+///     Mappable, MemoryKeyValueStore, MerkleRoot, StorageAsMut, StructuredStorage, Table,
+///     TableWithCodec,
+/// };
+///
+/// /// The storage slots of contracts, keyed by the contract id and the slot.
+/// pub struct ContractsState;
+///
+/// impl Mappable for ContractsState {
+///     type Key = ([u8; 32], [u8; 32]);
+///     type SetValue = [u8; 32];
+///     type GetValue = [u8; 32];
+/// }
+///
+/// impl TableWithCodec for ContractsState {
+///     type KeyCodec = Compact;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for ContractsState {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "ContractsState";
+/// }
+///
+/// impl SparseMerkleTable for ContractsState {
+///     type Kind = ValueHash;
+///     type Nodes = StateNodes;
+///     type Roots = StateRoots;
+///
+///     fn tree_key(key: &Self::Key) -> [u8; 32] {
+///         key.0
+///     }
+/// }
+///
+/// pub struct StateNodes;
+///
+/// impl Mappable for StateNodes {
+///     type Key = MerkleRoot;
+///     type SetValue = SparseNode;
+///     type GetValue = SparseNode;
+/// }
+///
+/// impl TableWithCodec for StateNodes {
+///     type KeyCodec = Array;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for StateNodes {
+///     const COLUMN: u32 = 1;
+///     const NAME: &'static str = "StateNodes";
+/// }
+///
+/// pub struct StateRoots;
+///
+/// impl Mappable for StateRoots {
+///     type Key = [u8; 32];
+///     type SetValue = MerkleRoot;
+///     type GetValue = MerkleRoot;
+/// }
+///
+/// impl TableWithCodec for StateRoots {
+///     type KeyCodec = Array;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for StateRoots {
+///     const COLUMN: u32 = 2;
+///     const NAME: &'static str = "StateRoots";
+/// }
+///
+/// let mut storage: SparseMerkleStorage<_> =
+///     SparseMerkleStorage::new(StructuredStorage::new(MemoryKeyValueStore::new()));
+/// let contract = [1; 32];
+/// assert_eq!(storage.storage::<ContractsState>().root(&contract), Ok([0; 32]));
+///
+/// storage.storage::<ContractsState>().insert(&(contract, [1; 32]), &[10; 32]).unwrap();
+/// let root = storage.storage::<ContractsState>().root(&contract).unwrap();
+///
+/// storage.storage::<ContractsState>().insert(&(contract, [2; 32]), &[20; 32]).unwrap();
+/// assert_ne!(storage.storage::<ContractsState>().root(&contract).unwrap(), root);
+///
+/// // Trees of other contracts are independent.
+/// storage.storage::<ContractsState>().insert(&([2; 32], [3; 32]), &[30; 32]).unwrap();
+///
+/// storage.storage::<ContractsState>().remove(&(contract, [2; 32])).unwrap();
+/// assert_eq!(storage.storage::<ContractsState>().root(&contract), Ok(root));
+///
+/// // The slots can be proven against the root without the storage.
+/// let (present, absent) = ((contract, [1; 32]), (contract, [2; 32]));
+/// let proof = storage.storage::<ContractsState>().prove(&contract, &present).unwrap();
+/// assert!(verify::<ContractsState, Sha256>(&root, &present, Some(&[10; 32]), &proof));
+/// let proof = storage.storage::<ContractsState>().prove(&contract, &absent).unwrap();
+/// assert!(verify::<ContractsState, Sha256>(&root, &absent, None, &proof));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct SparseMerkleStorage<S, H = Sha256> {
+    storage: S,
+    _hasher: PhantomData<H>,
+}
+
+impl<S, H> SparseMerkleStorage<S, H> {
+    /// Wrap the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Return the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Return the mutable underlying storage. Modifications of the tables of the trees through it
+    /// are not reflected in the roots.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Unwrap the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S, H: MerkleHasher> SparseMerkleStorage<S, H> {
+    /// Return the digest of the root of the tree, which is empty for the tree without entries.
+    pub(super) fn tree_root<Type, E>(
+        &self,
+        tree: &<Type::Roots as Mappable>::Key,
+    ) -> Result<DigestOf<Type>, E>
+    where
+        S: StorageInspect<Type::Roots, Error = E>,
+        Type: SparseMerkleTable,
+    {
+        let root = StorageInspect::<Type::Roots>::get(&self.storage, tree)?;
+        Ok(root.map(Cow::into_owned).unwrap_or(DigestOf::<Type>::EMPTY))
+    }
+
+    /// Store the nodes of the tree with the leaf of the entry with the `key` set to the `leaf`, or
+    /// without the entry if there is no leaf, and return the new root of the tree. The root itself
+    /// is not updated, so nothing changes if the entry isn't stored afterwards.
+    fn update_tree<Type, E>(
+        &mut self,
+        key: &Type::Key,
+        leaf: Option<<DigestOf<Type> as SparseDigest>::Leaf>,
+    ) -> Result<DigestOf<Type>, E>
+    where
+        S: StorageMutate<Type::Nodes, Error = E> + StorageMutate<Type::Roots, Error = E>,
+        E: From<MerkleError>,
+        Type: SparseMerkleTable,
+    {
+        let path = H::hash(&Type::KeyCodec::encode(key));
+        let root = self.tree_root::<Type, E>(&Type::tree_key(key))?;
+        update::<H, _, Type::Nodes, S>(&mut self.storage, root, &path, leaf)
+    }
+
+    /// Set the `root` of the tree of the entry with the `key`.
+    fn set_root<Type, E>(&mut self, key: &Type::Key, root: DigestOf<Type>) -> Result<(), E>
+    where
+        S: StorageMutate<Type::Roots, Error = E>,
+        Type: SparseMerkleTable,
+    {
+        let tree = Type::tree_key(key);
+        if root == DigestOf::<Type>::EMPTY {
+            StorageMutate::<Type::Roots>::remove(&mut self.storage, &tree)?;
+        } else {
+            StorageMutate::<Type::Roots>::insert(&mut self.storage, &tree, &root)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S, H, Type> StorageInspect<Type> for SparseMerkleStorage<S, H>
+where
+    S: StorageInspect<Type>,
+    Type: SparseMerkleTable,
+{
+    type Error = S::Error;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        self.storage.get(key)
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        self.storage.contains_key(key)
+    }
+}
+
+impl<S, H, Type, E> StorageMutate<Type> for SparseMerkleStorage<S, H>
+where
+    S: StorageMutate<Type, Error = E>
+        + StorageMutate<Type::Nodes, Error = E>
+        + StorageMutate<Type::Roots, Error = E>,
+    E: From<MerkleError>,
+    H: MerkleHasher,
+    Type: SparseMerkleTable,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        let leaf = Type::Kind::leaf::<H>(value);
+        let root = self.update_tree::<Type, E>(key, Some(leaf))?;
+        let previous = StorageMutate::<Type>::insert(&mut self.storage, key, value)?;
+        self.set_root::<Type, E>(key, root)?;
+        Ok(previous)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        let previous = StorageMutate::<Type>::remove(&mut self.storage, key)?;
+        if previous.is_some() {
+            let root = self.update_tree::<Type, E>(key, None)?;
+            self.set_root::<Type, E>(key, root)?;
+        }
+        Ok(previous)
+    }
+}
+
+impl<S, H, Type, E> StorageWrite<Type> for SparseMerkleStorage<S, H>
+where
+    S: StorageMutate<Type, Error = E>
+        + StorageMutate<Type::Nodes, Error = E>
+        + StorageMutate<Type::Roots, Error = E>,
+    E: From<MerkleError>,
+    H: MerkleHasher,
+    Type: SparseMerkleTable,
+{
+}
+
+impl<S, H, Type, E> StorageBatchMutate<Type> for SparseMerkleStorage<S, H>
+where
+    S: StorageMutate<Type, Error = E>
+        + StorageMutate<Type::Nodes, Error = E>
+        + StorageMutate<Type::Roots, Error = E>,
+    E: From<MerkleError>,
+    H: MerkleHasher,
+    Type: SparseMerkleTable,
+{
+}
+
+impl<S, H, Type> StorageIterate<Type> for SparseMerkleStorage<S, H>
+where
+    S: StorageIterate<Type>,
+    Type: SparseMerkleTable,
+{
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        self.storage.iter_range(start, end, direction)
+    }
+}
+
+impl<S, H, Type, E> MerkleRootStorage<<Type::Roots as Mappable>::Key, Type>
+    for SparseMerkleStorage<S, H>
+where
+    S: StorageMutate<Type, Error = E>
+        + StorageMutate<Type::Nodes, Error = E>
+        + StorageMutate<Type::Roots, Error = E>,
+    E: From<MerkleError>,
+    H: MerkleHasher,
+    Type: SparseMerkleTable,
+{
+    fn root(&mut self, key: &<Type::Roots as Mappable>::Key) -> Result<MerkleRoot, Self::Error> {
+        Ok(SparseDigest::hash(&self.tree_root::<Type, E>(key)?))
+    }
+}
+
+impl<S, H, Type, E> MerkleProofStorage<<Type::Roots as Mappable>::Key, Type>
+    for SparseMerkleStorage<S, H>
+where
+    S: StorageMutate<Type, Error = E>
+        + StorageMutate<Type::Nodes, Error = E>
+        + StorageMutate<Type::Roots, Error = E>,
+    E: From<MerkleError>,
+    H: MerkleHasher,
+    Type: SparseMerkleTable,
+{
+    type Proof = SparseMerkleProof<DigestOf<Type>>;
+
+    fn prove(
+        &mut self,
+        key: &<Type::Roots as Mappable>::Key,
+        storage_key: &Type::Key,
+    ) -> Result<Self::Proof, Self::Error> {
+        let root = self.tree_root::<Type, E>(key)?;
+        let path = H::hash(&Type::KeyCodec::encode(storage_key));
+        let Descent { siblings, leaf, .. } =
+            descend::<_, Type::Nodes, S>(&self.storage, root, &path)?;
+        Ok(SparseMerkleProof { siblings, leaf })
+    }
+}
+
+impl<S, H, Type, E> MerkleMultiproofStorage<<Type::Roots as Mappable>::Key, Type>
+    for SparseMerkleStorage<S, H>
+where
+    S: StorageMutate<Type, Error = E>
+        + StorageMutate<Type::Nodes, Error = E>
+        + StorageMutate<Type::Roots, Error = E>,
+    E: From<MerkleError>,
+    H: MerkleHasher,
+    Type: SparseMerkleTable,
+{
+    type Multiproof = SparseMerkleMultiproof<DigestOf<Type>>;
+
+    fn prove_many(
+        &mut self,
+        key: &<Type::Roots as Mappable>::Key,
+        storage_keys: &[Type::Key],
+    ) -> Result<Self::Multiproof, Self::Error> {
+        let root = self.tree_root::<Type, E>(key)?;
+        let mut paths: Vec<_> = storage_keys
+            .iter()
+            .map(|key| H::hash(&Type::KeyCodec::encode(key)))
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+        multiproof::prove::<_, Type::Nodes, S>(&self.storage, root, &paths)
+    }
+}
+
+/// The table of nodes of trees with the `D` digest.
+pub(crate) trait NodesTable<D: SparseDigest>:
+    Mappable<Key = MerkleRoot, SetValue = SparseNode<D>, GetValue = SparseNode<D>>
+{
+}
+
+impl<D, T> NodesTable<D> for T
+where
+    D: SparseDigest,
+    T: Mappable<Key = MerkleRoot, SetValue = SparseNode<D>, GetValue = SparseNode<D>>,
+{
+}
+
+/// The subtree rebuilt on the way from the updated leaf to the root.
+#[derive(Clone, Copy)]
+enum Subtree<D> {
+    Empty,
+    Leaf(D),
+    Branch(D),
+}
+
+/// The depth of the leaves at the full length of paths, below which there are no branches.
+pub(crate) const MAX_DEPTH: usize = core::mem::size_of::<MerkleRoot>() * 8;
+
+/// Return `true` if the `path` goes to the right half of the subtree at the `depth`.
+pub(crate) fn goes_right(path: &MerkleRoot, depth: usize) -> bool {
+    path[depth / 8] >> (7 - depth % 8) & 1 == 1
+}
+
+/// Return the number of the leading bits shared by both paths.
+pub(crate) fn common_prefix_len(first: &MerkleRoot, second: &MerkleRoot) -> usize {
+    for (i, (a, b)) in first.iter().zip(second).enumerate() {
+        if a != b {
+            return i * 8 + (a ^ b).leading_zeros() as usize;
+        }
+    }
+    first.len() * 8
+}
+
+pub(crate) fn load<D, Nodes, S>(storage: &S, digest: &D) -> Result<SparseNode<D>, S::Error>
+where
+    D: SparseDigest,
+    Nodes: NodesTable<D>,
+    S: StorageInspect<Nodes>,
+    S::Error: From<MerkleError>,
+{
+    let hash = SparseDigest::hash(digest);
+    let node = storage.get(&hash)?.ok_or(MerkleError::MissingNode(hash))?;
+    Ok(node.into_owned())
+}
+
+/// The nodes created by the update, stored only once all of them are computed.
+type Staged<D> = Vec<(MerkleRoot, SparseNode<D>)>;
+
+fn stage<H, D>(staged: &mut Staged<D>, node: SparseNode<D>) -> Result<D, MerkleError>
+where
+    H: MerkleHasher,
+    D: SparseDigest,
+{
+    let digest = node.digest::<H>()?;
+    staged.push((SparseDigest::hash(&digest), node));
+    Ok(digest)
+}
+
+/// Stage the branch with the `child` on the side of the `path` at the `depth` and the `sibling` on
+/// the other side.
+fn stage_branch<H, D>(
+    staged: &mut Staged<D>,
+    path: &MerkleRoot,
+    depth: usize,
+    child: D,
+    sibling: D,
+) -> Result<D, MerkleError>
+where
+    H: MerkleHasher,
+    D: SparseDigest,
+{
+    let (left, right) = if goes_right(path, depth) {
+        (sibling, child)
+    } else {
+        (child, sibling)
+    };
+    stage::<H, D>(staged, SparseNode::Branch { left, right })
+}
+
+/// The way from the root of the tree down to the `path`.
+pub(crate) struct Descent<D: SparseDigest> {
+    /// The siblings of the nodes on the way, starting from the top.
+    pub siblings: Vec<D>,
+    /// The digest of the subtree where the way ends: the empty subtree or the leaf.
+    pub terminal: D,
+    /// The path and the data of the leaf where the way ends.
+    pub leaf: Option<(MerkleRoot, D::Leaf)>,
+}
+
+/// Go down the tree with the `root` along the `path` until the empty subtree or the leaf.
+pub(crate) fn descend<D, Nodes, S>(
+    storage: &S,
+    root: D,
+    path: &MerkleRoot,
+) -> Result<Descent<D>, S::Error>
+where
+    D: SparseDigest,
+    Nodes: NodesTable<D>,
+    S: StorageInspect<Nodes>,
+    S::Error: From<MerkleError>,
+{
+    let mut siblings = Vec::new();
+    let mut current = root;
+    let leaf = loop {
+        if current == D::EMPTY {
+            break None;
+        }
+        match load::<D, Nodes, S>(storage, &current)? {
+            SparseNode::Branch { .. } if siblings.len() == MAX_DEPTH => {
+                return Err(MerkleError::TooDeep.into());
+            }
+            SparseNode::Branch { left, right } => {
+                if goes_right(path, siblings.len()) {
+                    siblings.push(left);
+                    current = right;
+                } else {
+                    siblings.push(right);
+                    current = left;
+                }
+            }
+            SparseNode::Leaf { path, data } => break Some((path, data)),
+        }
+    };
+    Ok(Descent {
+        siblings,
+        terminal: current,
+        leaf,
+    })
+}
+
+/// Set the `data` of the leaf at the `path` of the tree with the `root`, or remove the leaf if
+/// there is no data, and return the new root.
+pub(crate) fn update<H, D, Nodes, S>(
+    storage: &mut S,
+    root: D,
+    path: &MerkleRoot,
+    data: Option<D::Leaf>,
+) -> Result<D, S::Error>
+where
+    H: MerkleHasher,
+    D: SparseDigest,
+    Nodes: NodesTable<D>,
+    S: StorageMutate<Nodes>,
+    S::Error: From<MerkleError>,
+{
+    let Descent {
+        siblings,
+        terminal: current,
+        leaf,
+    } = descend::<D, Nodes, S>(storage, root, path)?;
+    let terminal = leaf.map(|(leaf_path, _)| leaf_path);
+
+    let depth = siblings.len();
+    let mut staged = Staged::new();
+    let mut subtree = match (terminal, data) {
+        (Some(leaf_path), Some(data)) if leaf_path != *path => {
+            // The subtree of the other leaf is split down to the first bit where the paths differ.
+            let split = common_prefix_len(path, &leaf_path);
+            if split >= MAX_DEPTH {
+                return Err(MerkleError::TooDeep.into());
+            }
+            let leaf = stage::<H, D>(&mut staged, SparseNode::Leaf { path: *path, data })?;
+            let mut digest = stage_branch::<H, D>(&mut staged, path, split, leaf, current)?;
+            for depth in (depth..split).rev() {
+                digest = stage_branch::<H, D>(&mut staged, path, depth, digest, D::EMPTY)?;
+            }
+            Subtree::Branch(digest)
+        }
+        (_, Some(data)) => Subtree::Leaf(stage::<H, D>(
+            &mut staged,
+            SparseNode::Leaf { path: *path, data },
+        )?),
+        (Some(leaf_path), None) if leaf_path == *path => Subtree::Empty,
+        (_, None) => return Ok(root),
+    };
+
+    // The leaf left alone in its subtree replaces the subtree on the way up.
+    for (depth, sibling) in siblings.into_iter().enumerate().rev() {
+        subtree = match subtree {
+            Subtree::Empty if sibling == D::EMPTY => Subtree::Empty,
+            Subtree::Empty => match load::<D, Nodes, S>(storage, &sibling)? {
+                SparseNode::Leaf { .. } => Subtree::Leaf(sibling),
+                SparseNode::Branch { .. } => Subtree::Branch(stage_branch::<H, D>(
+                    &mut staged,
+                    path,
+                    depth,
+                    D::EMPTY,
+                    sibling,
+                )?),
+            },
+            Subtree::Leaf(digest) if sibling == D::EMPTY => Subtree::Leaf(digest),
+            Subtree::Leaf(digest) | Subtree::Branch(digest) => Subtree::Branch(
+                stage_branch::<H, D>(&mut staged, path, depth, digest, sibling)?,
+            ),
+        };
+    }
+
+    // Nothing is stored before the whole update is known to succeed, so the failed update, like
+    // the one overflowing the sum, leaves the nodes unchanged.
+    for (hash, node) in staged {
+        storage.insert(&hash, &node)?;
+    }
+
+    match subtree {
+        Subtree::Empty => Ok(D::EMPTY),
+        Subtree::Leaf(digest) | Subtree::Branch(digest) => Ok(digest),
+    }
+}
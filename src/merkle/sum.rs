@@ -0,0 +1,175 @@
+use super::{
+    sparse::{BRANCH_PREFIX, LEAF_PREFIX},
+    MerkleError, MerkleHasher, SparseDigest, SparseMerkleStorage, SparseMerkleTable,
+    SparseTreeKind,
+};
+use crate::{Mappable, MerkleRoot, MerkleSumRootStorage, StorageMutate};
+
+/// The hash and the sum of the subtree is the digest of sum trees. The leaf is hashed as
+/// `H(0x00 || path || value)` and the branch as `H(0x01 || left || left_sum || right ||
+/// right_sum)`, where sums are 8 bytes in the big-endian order, so the root commits to the total
+/// of the tree.
+impl SparseDigest for (MerkleRoot, u64) {
+    type Leaf = u64;
+
+    const EMPTY: Self = ([0; 32], 0);
+
+    fn hash(&self) -> MerkleRoot {
+        self.0
+    }
+
+    fn leaf<H: MerkleHasher>(path: &MerkleRoot, data: &u64) -> Self {
+        let mut hasher = H::default();
+        hasher.update(&[LEAF_PREFIX]);
+        hasher.update(path);
+        hasher.update(&data.to_be_bytes());
+        (hasher.finalize(), *data)
+    }
+
+    fn branch<H: MerkleHasher>(left: &Self, right: &Self) -> Result<Self, MerkleError> {
+        let sum = left
+            .1
+            .checked_add(right.1)
+            .ok_or(MerkleError::SumOverflow)?;
+        let mut hasher = H::default();
+        hasher.update(&[BRANCH_PREFIX]);
+        hasher.update(&left.0);
+        hasher.update(&left.1.to_be_bytes());
+        hasher.update(&right.0);
+        hasher.update(&right.1.to_be_bytes());
+        Ok((hasher.finalize(), sum))
+    }
+}
+
+/// The kind of sparse Merkle trees over `u64` values, whose nodes commit to the sums of the values
+/// below them. The [`SparseMerkleStorage`] implements the [`MerkleSumRootStorage`] for them, and
+/// their proofs carry the sums of the siblings, so the proven value can be checked against the
+/// total.
+///
+/// The insertion that would make the total overflow the `u64` fails with the
+/// [`MerkleError::SumOverflow`] and leaves the storage unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::{Array, BigEndian, Compact},
+///     merkle::{verify, Sha256, SparseMerkleStorage, SparseMerkleTable, SparseNode, Sum},
+///     Mappable, MemoryKeyValueStore, MerkleRoot, StorageAsMut, StorageError, StructuredStorage,
+///     Table, TableWithCodec,
+/// };
+///
+/// /// The balances of assets owned by accounts, keyed by the account and the asset.
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = ([u8; 32], [u8; 32]);
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// impl TableWithCodec for Balances {
+///     type KeyCodec = Compact;
+///     type ValueCodec = BigEndian;
+/// }
+///
+/// impl Table for Balances {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "Balances";
+/// }
+///
+/// impl SparseMerkleTable for Balances {
+///     type Kind = Sum;
+///     type Nodes = BalancesNodes;
+///     type Roots = BalancesRoots;
+///
+///     fn tree_key(key: &Self::Key) -> [u8; 32] {
+///         key.0
+///     }
+/// }
+///
+/// pub struct BalancesNodes;
+///
+/// impl Mappable for BalancesNodes {
+///     type Key = MerkleRoot;
+///     type SetValue = SparseNode<(MerkleRoot, u64)>;
+///     type GetValue = SparseNode<(MerkleRoot, u64)>;
+/// }
+///
+/// impl TableWithCodec for BalancesNodes {
+///     type KeyCodec = Array;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for BalancesNodes {
+///     const COLUMN: u32 = 1;
+///     const NAME: &'static str = "BalancesNodes";
+/// }
+///
+/// pub struct BalancesRoots;
+///
+/// impl Mappable for BalancesRoots {
+///     type Key = [u8; 32];
+///     type SetValue = (MerkleRoot, u64);
+///     type GetValue = (MerkleRoot, u64);
+/// }
+///
+/// impl TableWithCodec for BalancesRoots {
+///     type KeyCodec = Array;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for BalancesRoots {
+///     const COLUMN: u32 = 2;
+///     const NAME: &'static str = "BalancesRoots";
+/// }
+///
+/// let mut storage: SparseMerkleStorage<_> =
+///     SparseMerkleStorage::new(StructuredStorage::new(MemoryKeyValueStore::new()));
+/// let account = [1; 32];
+/// storage.storage::<Balances>().insert(&(account, [1; 32]), &100).unwrap();
+/// storage.storage::<Balances>().insert(&(account, [2; 32]), &50).unwrap();
+/// let (root, total) = storage.storage::<Balances>().root_and_sum(&account).unwrap();
+/// assert_eq!(total, 150);
+/// assert_eq!(storage.storage::<Balances>().root(&account), Ok(root));
+///
+/// // The proof commits to the sums of the siblings.
+/// let proof = storage.storage::<Balances>().prove(&account, &(account, [1; 32])).unwrap();
+/// assert_eq!(proof.siblings.iter().map(|(_, sum)| sum).sum::<u64>(), 50);
+/// assert!(verify::<Balances, Sha256>(&(root, total), &(account, [1; 32]), Some(&100), &proof));
+/// assert!(!verify::<Balances, Sha256>(&(root, 200), &(account, [1; 32]), Some(&100), &proof));
+///
+/// // The total can't overflow.
+/// let overflow = storage.storage::<Balances>().insert(&(account, [3; 32]), &u64::MAX);
+/// assert!(matches!(overflow, Err(StorageError::Merkle(_))));
+/// assert_eq!(storage.storage::<Balances>().get(&(account, [3; 32])), Ok(None));
+/// assert_eq!(storage.storage::<Balances>().root_and_sum(&account), Ok((root, total)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sum;
+
+impl<Type: Mappable<SetValue = u64> + ?Sized> SparseTreeKind<Type> for Sum {
+    type Digest = (MerkleRoot, u64);
+
+    fn leaf<H: MerkleHasher>(value: &u64) -> u64 {
+        *value
+    }
+}
+
+impl<S, H, Type, E> MerkleSumRootStorage<<Type::Roots as Mappable>::Key, Type>
+    for SparseMerkleStorage<S, H>
+where
+    S: StorageMutate<Type, Error = E>
+        + StorageMutate<Type::Nodes, Error = E>
+        + StorageMutate<Type::Roots, Error = E>,
+    E: From<MerkleError>,
+    H: MerkleHasher,
+    Type: SparseMerkleTable<Kind = Sum, SetValue = u64>,
+{
+    fn root_and_sum(
+        &mut self,
+        key: &<Type::Roots as Mappable>::Key,
+    ) -> Result<(MerkleRoot, u64), Self::Error> {
+        self.tree_root::<Type, E>(key)
+    }
+}
@@ -0,0 +1,357 @@
+use super::{
+    multiproof::recompute, DigestOf, MerkleError, MerkleHasher, SparseDigest,
+    SparseMerkleMultiproof, SparseMerkleTable, SparseTreeKind,
+};
+use crate::{
+    borrow,
+    codec::{CompactDecode, CompactEncode, Decode, DecodeError, Encode},
+    Mappable, MerkleMultiproofStorage, MerkleRoot, MerkleRootStorage, StorageInspect,
+};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeMap,
+    vec::Vec,
+};
+use core::fmt;
+
+/// The witness of the accesses to the entries of one sparse Merkle tree: the values of the
+/// entries before the accesses and their multiproof against the root of the tree.
+pub struct TreeWitness<Type: SparseMerkleTable> {
+    /// The digest of the root of the tree.
+    pub root: DigestOf<Type>,
+    /// The accessed entries, sorted by the key, with their values or `None` for absent ones.
+    pub entries: Vec<(Type::Key, Option<Type::GetValue>)>,
+    /// The multiproof of the entries.
+    pub proof: SparseMerkleMultiproof<DigestOf<Type>>,
+}
+
+/// The state witness of the accesses to the [`SparseMerkleTable`]: the [`TreeWitness`] of every
+/// tree with accessed entries. It is generated from the storage in the state before the accesses,
+/// usually from the keys recorded by the [`RecordingStorage`](crate::RecordingStorage), and served
+/// to stateless validators by the [`ProvenStorage`](super::ProvenStorage).
+///
+/// The binary format starts with the [`VERSION`](Self::VERSION) byte followed by the
+/// [`Compact`](crate::codec::Compact) encoding of the number of trees and every tree: the root,
+/// the number of entries, every entry as the key encoded by the key codec of the table and the
+/// optional value encoded by its value codec, both as byte strings, and the multiproof.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::{Array, Compact},
+///     merkle::{
+///         ProvenStorage, Sha256, SparseMerkleStorage, SparseMerkleTable, SparseNode,
+///         StateWitness, ValueHash,
+///     },
+///     Mappable, MemoryKeyValueStore, MerkleRoot, RecordingStorage, StorageAsMut,
+///     StorageTransaction, StructuredStorage, Table, TableWithCodec,
+/// };
+///
+/// /// The storage slots of contracts, keyed by the contract id and the slot.
+/// pub struct ContractsState;
+///
+/// impl Mappable for ContractsState {
+///     type Key = ([u8; 32], [u8; 32]);
+///     type SetValue = [u8; 32];
+///     type GetValue = [u8; 32];
+/// }
+///
+/// impl TableWithCodec for ContractsState {
+///     type KeyCodec = Compact;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for ContractsState {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "ContractsState";
+/// }
+///
+/// impl SparseMerkleTable for ContractsState {
+///     type Kind = ValueHash;
+///     type Nodes = StateNodes;
+///     type Roots = StateRoots;
+///
+///     fn tree_key(key: &Self::Key) -> [u8; 32] {
+///         key.0
+///     }
+/// }
+///
+/// pub struct StateNodes;
+///
+/// impl Mappable for StateNodes {
+///     type Key = MerkleRoot;
+///     type SetValue = SparseNode;
+///     type GetValue = SparseNode;
+/// }
+///
+/// impl TableWithCodec for StateNodes {
+///     type KeyCodec = Array;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for StateNodes {
+///     const COLUMN: u32 = 1;
+///     const NAME: &'static str = "StateNodes";
+/// }
+///
+/// pub struct StateRoots;
+///
+/// impl Mappable for StateRoots {
+///     type Key = [u8; 32];
+///     type SetValue = MerkleRoot;
+///     type GetValue = MerkleRoot;
+/// }
+///
+/// impl TableWithCodec for StateRoots {
+///     type KeyCodec = Array;
+///     type ValueCodec = Array;
+/// }
+///
+/// impl Table for StateRoots {
+///     const COLUMN: u32 = 2;
+///     const NAME: &'static str = "StateRoots";
+/// }
+///
+/// let mut storage: SparseMerkleStorage<_> =
+///     SparseMerkleStorage::new(StructuredStorage::new(MemoryKeyValueStore::new()));
+/// let contract = [1; 32];
+/// for slot in 0..8 {
+///     storage.storage::<ContractsState>().insert(&(contract, [slot; 32]), &[slot; 32]).unwrap();
+/// }
+/// let root = storage.storage::<ContractsState>().root(&contract).unwrap();
+///
+/// // The block is executed once to record its accesses, and the changes are discarded.
+/// let mut recording = RecordingStorage::new(StorageTransaction::new(&mut storage));
+/// recording.storage::<ContractsState>().get(&(contract, [3; 32])).unwrap();
+/// recording.storage::<ContractsState>().insert(&(contract, [42; 32]), &[42; 32]).unwrap();
+/// // The witness proves both the read and the written keys.
+/// let reads = recording.reads::<ContractsState>();
+/// let writes = recording.writes::<ContractsState>();
+/// drop(recording);
+///
+/// let keys = reads.keys().chain(writes.keys());
+/// let witness =
+///     StateWitness::<ContractsState>::generate::<_, Sha256>(&mut storage, keys).unwrap();
+/// let bytes = witness.encode();
+///
+/// // The validator re-executes the block from the witness alone.
+/// let witness = StateWitness::<ContractsState>::decode(&bytes).unwrap();
+/// let mut proven = ProvenStorage::<ContractsState>::from_witness(&witness.trees[0]).unwrap();
+/// assert_eq!(proven.root(), root);
+/// assert_eq!(
+///     proven.storage::<ContractsState>().get(&(contract, [3; 32])).unwrap().unwrap().into_owned(),
+///     [3; 32]
+/// );
+/// proven.storage::<ContractsState>().insert(&(contract, [42; 32]), &[42; 32]).unwrap();
+///
+/// storage.storage::<ContractsState>().insert(&(contract, [42; 32]), &[42; 32]).unwrap();
+/// assert_eq!(Ok(proven.root()), storage.storage::<ContractsState>().root(&contract));
+/// ```
+pub struct StateWitness<Type: SparseMerkleTable> {
+    /// The witnesses of the trees, sorted by the tree key.
+    pub trees: Vec<TreeWitness<Type>>,
+}
+
+// The derived implementations would require the `Type` itself to implement the traits.
+impl<Type> fmt::Debug for TreeWitness<Type>
+where
+    Type: SparseMerkleTable,
+    Type::Key: fmt::Debug,
+    Type::GetValue: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TreeWitness")
+            .field("root", &self.root)
+            .field("entries", &self.entries)
+            .field("proof", &self.proof)
+            .finish()
+    }
+}
+
+impl<Type> Clone for TreeWitness<Type>
+where
+    Type: SparseMerkleTable,
+    Type::Key: Clone,
+    Type::GetValue: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root,
+            entries: self.entries.clone(),
+            proof: self.proof.clone(),
+        }
+    }
+}
+
+impl<Type> fmt::Debug for StateWitness<Type>
+where
+    Type: SparseMerkleTable,
+    Type::Key: fmt::Debug,
+    Type::GetValue: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateWitness")
+            .field("trees", &self.trees)
+            .finish()
+    }
+}
+
+impl<Type> Clone for StateWitness<Type>
+where
+    Type: SparseMerkleTable,
+    Type::Key: Clone,
+    Type::GetValue: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            trees: self.trees.clone(),
+        }
+    }
+}
+
+impl<Type> StateWitness<Type>
+where
+    Type: SparseMerkleTable,
+    Type::Key: Ord + Clone,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    <Type::Roots as Mappable>::Key: Ord,
+{
+    /// The version of the binary format.
+    pub const VERSION: u8 = 1;
+
+    /// Generate the witness of the entries with the `keys` from the `storage`, which should be in
+    /// the state before the accesses. The `H` should be the same as used by the storage.
+    pub fn generate<'a, S, H>(
+        storage: &mut S,
+        keys: impl IntoIterator<Item = &'a Type::Key>,
+    ) -> Result<Self, S::Error>
+    where
+        S: MerkleMultiproofStorage<
+            <Type::Roots as Mappable>::Key,
+            Type,
+            Multiproof = SparseMerkleMultiproof<DigestOf<Type>>,
+        >,
+        S::Error: From<MerkleError>,
+        H: MerkleHasher,
+        Type::Key: 'a,
+    {
+        let mut trees = BTreeMap::<_, Vec<Type::Key>>::new();
+        for key in keys {
+            trees
+                .entry(Type::tree_key(key))
+                .or_default()
+                .push(key.clone());
+        }
+
+        let mut witnesses = Vec::with_capacity(trees.len());
+        for (tree, mut keys) in trees {
+            keys.sort();
+            keys.dedup();
+            let proof = storage.prove_many(&tree, &keys)?;
+            let mut entries = Vec::with_capacity(keys.len());
+            for key in keys {
+                let value = StorageInspect::<Type>::get(storage, &key)?.map(Cow::into_owned);
+                entries.push((key, value));
+            }
+
+            // The multiproof commits to the digest of the root, which includes more than its hash
+            // for some kinds of trees.
+            let (root, _) = recompute::<_, H>(leaves::<Type, H>(&entries), &proof)
+                .ok_or(MerkleError::InvalidProof)?;
+            if SparseDigest::hash(&root) != MerkleRootStorage::<_, Type>::root(storage, &tree)? {
+                return Err(MerkleError::InvalidProof.into());
+            }
+            witnesses.push(TreeWitness {
+                root,
+                entries,
+                proof,
+            });
+        }
+        Ok(Self { trees: witnesses })
+    }
+
+    /// Return the encoding of the witness in the binary format.
+    pub fn encode(&self) -> Vec<u8>
+    where
+        DigestOf<Type>: CompactEncode,
+        <DigestOf<Type> as SparseDigest>::Leaf: CompactEncode,
+    {
+        let mut buf = alloc::vec![Self::VERSION];
+        self.trees.len().encode_compact(&mut buf);
+        for tree in &self.trees {
+            tree.root.encode_compact(&mut buf);
+            tree.entries.len().encode_compact(&mut buf);
+            for (key, value) in &tree.entries {
+                Type::KeyCodec::encode(key).encode_compact(&mut buf);
+                match value {
+                    Some(value) => {
+                        buf.push(1);
+                        Type::ValueCodec::encode(borrow::<Type::SetValue>(value))
+                            .encode_compact(&mut buf);
+                    }
+                    None => buf.push(0),
+                }
+            }
+            tree.proof.encode_compact(&mut buf);
+        }
+        buf
+    }
+
+    /// Decode the witness from the binary format.
+    pub fn decode(mut bytes: &[u8]) -> Result<Self, DecodeError>
+    where
+        DigestOf<Type>: CompactDecode,
+        <DigestOf<Type> as SparseDigest>::Leaf: CompactDecode,
+    {
+        let bytes = &mut bytes;
+        let version = u8::decode_compact(bytes)?;
+        if version != Self::VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let mut trees = Vec::new();
+        for _ in 0..usize::decode_compact(bytes)? {
+            let root = CompactDecode::decode_compact(bytes)?;
+            let mut entries = Vec::new();
+            for _ in 0..usize::decode_compact(bytes)? {
+                let key = Type::KeyCodec::decode(&Vec::<u8>::decode_compact(bytes)?)?;
+                let value = Option::<Vec<u8>>::decode_compact(bytes)?
+                    .map(|value| Type::ValueCodec::decode(&value))
+                    .transpose()?;
+                entries.push((key, value));
+            }
+            let proof = CompactDecode::decode_compact(bytes)?;
+            trees.push(TreeWitness {
+                root,
+                entries,
+                proof,
+            });
+        }
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(Self { trees })
+    }
+}
+
+/// Return the paths and the leaf data of the `entries` of the tree.
+fn leaves<Type, H>(
+    entries: &[(Type::Key, Option<Type::GetValue>)],
+) -> Vec<(MerkleRoot, Option<<DigestOf<Type> as SparseDigest>::Leaf>)>
+where
+    Type: SparseMerkleTable,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    H: MerkleHasher,
+{
+    entries
+        .iter()
+        .map(|(key, value)| {
+            let path = H::hash(&Type::KeyCodec::encode(key));
+            let leaf = value.as_ref().map(|value| {
+                <Type::Kind as SparseTreeKind<Type>>::leaf::<H>(borrow::<Type::SetValue>(value))
+            });
+            (path, leaf)
+        })
+        .collect()
+}
@@ -0,0 +1,251 @@
+use crate::{Mappable, StorageBatchMutate, StorageInspect, StorageMutate, StorageWrite};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+};
+use core::{
+    any::{Any, TypeId},
+    cell::RefCell,
+};
+
+/// The accesses to the entries of one table: the observed or written value of every accessed key,
+/// `None` if there is no value.
+pub type Accesses<Type> = BTreeMap<<Type as Mappable>::Key, Option<<Type as Mappable>::GetValue>>;
+
+/// The type-erased [`Accesses`] per table, tables are distinguished by the type of the `Mappable`.
+type Records = BTreeMap<TypeId, Box<dyn Any>>;
+
+/// The wrapper around the storage that records the keys read and written through it, per table.
+///
+/// The read set contains the value of every key read before it was written, and the write set
+/// contains the last value written to every key, `None` if it was removed. The values replaced by
+/// the first write of every key are recorded in their own set, so written keys aren't reported as
+/// read. The `contains_key` fetches the value to record it. The recorded sets are used to build
+/// execution witnesses, to detect conflicts between transactions executed in parallel and to debug
+/// unexpected accesses.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{Mappable, MemoryStorage, RecordingStorage, StorageAsMut};
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = MemoryStorage::new();
+/// storage.storage::<Balances>().insert(&1, &100).unwrap();
+///
+/// let mut recording = RecordingStorage::new(&mut storage);
+/// recording.storage::<Balances>().get(&1).unwrap();
+/// assert!(!recording.storage::<Balances>().contains_key(&2).unwrap());
+/// recording.storage::<Balances>().insert(&1, &50).unwrap();
+/// recording.storage::<Balances>().remove(&3).unwrap();
+///
+/// let reads = recording.reads::<Balances>();
+/// assert_eq!(reads.into_iter().collect::<Vec<_>>(), [(1, Some(100)), (2, None)]);
+/// let previous = recording.previous::<Balances>();
+/// assert_eq!(previous.into_iter().collect::<Vec<_>>(), [(1, Some(100)), (3, None)]);
+/// let writes = recording.writes::<Balances>();
+/// assert_eq!(writes.into_iter().collect::<Vec<_>>(), [(1, Some(50)), (3, None)]);
+/// ```
+#[derive(Debug, Default)]
+pub struct RecordingStorage<S> {
+    storage: S,
+    reads: RefCell<Records>,
+    previous: Records,
+    writes: Records,
+}
+
+impl<S> RecordingStorage<S> {
+    /// Wrap the `storage` without recorded accesses.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            reads: RefCell::new(Records::new()),
+            previous: Records::new(),
+            writes: Records::new(),
+        }
+    }
+
+    /// Return the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Unwrap the underlying storage, dropping the recorded accesses.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Return the keys of the `Type` read before being written with their values at the first
+    /// reads.
+    pub fn reads<Type>(&self) -> Accesses<Type>
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::GetValue: Clone + 'static,
+    {
+        recorded::<Type>(&self.reads.borrow())
+    }
+
+    /// Return the keys of the `Type` written so far with their values before the first writes.
+    pub fn previous<Type>(&self) -> Accesses<Type>
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::GetValue: Clone + 'static,
+    {
+        recorded::<Type>(&self.previous)
+    }
+
+    /// Return the keys of the `Type` written so far with the values written last, `None` for the
+    /// removed ones.
+    pub fn writes<Type>(&self) -> Accesses<Type>
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::GetValue: Clone + 'static,
+    {
+        recorded::<Type>(&self.writes)
+    }
+
+    /// Forget all recorded accesses.
+    pub fn clear(&mut self) {
+        self.reads.get_mut().clear();
+        self.previous.clear();
+        self.writes.clear();
+    }
+
+    fn record_read<Type>(&self, key: &Type::Key, value: Option<&Type::GetValue>)
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::GetValue: Clone + 'static,
+    {
+        // The reads of the own writes don't depend on the state before the accesses.
+        let written = self
+            .writes
+            .get(&TypeId::of::<Type>())
+            .and_then(|accesses| accesses.downcast_ref::<Accesses<Type>>())
+            .is_some_and(|writes| writes.contains_key(key));
+        let mut reads = self.reads.borrow_mut();
+        let reads = accesses_mut::<Type>(&mut reads);
+        if !written && !reads.contains_key(key) {
+            reads.insert(key.clone(), value.cloned());
+        }
+    }
+
+    fn record_write<Type>(
+        &mut self,
+        key: &Type::Key,
+        previous: Option<&Type::GetValue>,
+        value: Option<Type::GetValue>,
+    ) where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::GetValue: Clone + 'static,
+    {
+        let previous_values = accesses_mut::<Type>(&mut self.previous);
+        if !previous_values.contains_key(key) {
+            previous_values.insert(key.clone(), previous.cloned());
+        }
+        accesses_mut::<Type>(&mut self.writes).insert(key.clone(), value);
+    }
+}
+
+fn recorded<Type>(records: &Records) -> Accesses<Type>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: Clone + 'static,
+{
+    records
+        .get(&TypeId::of::<Type>())
+        .and_then(|accesses| accesses.downcast_ref::<Accesses<Type>>())
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn accesses_mut<Type>(records: &mut Records) -> &mut Accesses<Type>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + 'static,
+    Type::GetValue: 'static,
+{
+    records
+        .entry(TypeId::of::<Type>())
+        .or_insert_with(|| Box::new(Accesses::<Type>::new()))
+        .downcast_mut()
+        .expect("The accesses are always created with the type of the `Mappable`")
+}
+
+impl<Type, S> StorageInspect<Type> for RecordingStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: Clone + 'static,
+    S: StorageInspect<Type>,
+{
+    type Error = S::Error;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        let value = self.storage.get(key)?;
+        self.record_read::<Type>(key, value.as_deref());
+        Ok(value)
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        Ok(StorageInspect::<Type>::get(self, key)?.is_some())
+    }
+}
+
+impl<Type, S> StorageMutate<Type> for RecordingStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: Clone + 'static,
+    S: StorageMutate<Type>,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        let previous = self.storage.insert(key, value)?;
+        self.record_write::<Type>(key, previous.as_ref(), Some(value.to_owned()));
+        Ok(previous)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        let previous = self.storage.remove(key)?;
+        self.record_write::<Type>(key, previous.as_ref(), None);
+        Ok(previous)
+    }
+}
+
+impl<Type, S> StorageWrite<Type> for RecordingStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: Clone + 'static,
+    S: StorageMutate<Type>,
+{
+}
+
+impl<Type, S> StorageBatchMutate<Type> for RecordingStorage<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: Clone + 'static,
+    S: StorageMutate<Type>,
+{
+}
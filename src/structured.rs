@@ -0,0 +1,217 @@
+use crate::{
+    codec::{Decode, DecodeError, Encode},
+    kv_store::{IterableKeyValueStore, KeyValueStore},
+    BoxedIter, IterDirection, KVItem, StorageBatchMutate, StorageInspect, StorageIterate,
+    StorageMutate, StorageWrite, Table, TableWithCodec,
+};
+use alloc::{borrow::Cow, boxed::Box};
+use core::ops::Bound;
+
+/// The adapter that serves every [`Table`] with the [`TableWithCodec`] from the [`KeyValueStore`].
+/// Keys and values of the table are encoded with its codecs and stored under its column.
+///
+/// The error of the [`KeyValueStore`] should be convertible from the [`DecodeError`] to report
+/// stored bytes that can't be decoded.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::{BigEndian, Raw},
+///     Mappable, MemoryKeyValueStore, StorageAsMut, StructuredStorage, Table, TableWithCodec,
+/// };
+///
+/// pub struct Contracts;
+///
+/// impl Mappable for Contracts {
+///     type Key = u32;
+///     type SetValue = [u8];
+///     type GetValue = Vec<u8>;
+/// }
+///
+/// impl TableWithCodec for Contracts {
+///     type KeyCodec = BigEndian;
+///     type ValueCodec = Raw;
+/// }
+///
+/// impl Table for Contracts {
+///     const COLUMN: u32 = 1;
+///     const NAME: &'static str = "Contracts";
+/// }
+///
+/// let mut storage = StructuredStorage::new(MemoryKeyValueStore::new());
+/// storage.storage::<Contracts>().insert(&7, &[1, 2, 3]).unwrap();
+///
+/// assert_eq!(
+///     storage.storage::<Contracts>().get(&7).unwrap().unwrap().into_owned(),
+///     vec![1, 2, 3]
+/// );
+/// assert_eq!(
+///     storage.inner().column(1).unwrap().get(&7u32.to_be_bytes()[..]),
+///     Some(&vec![1, 2, 3])
+/// );
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct StructuredStorage<S> {
+    storage: S,
+}
+
+impl<S> StructuredStorage<S> {
+    /// Wrap the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Return the underlying storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Return the mutable underlying storage.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Unwrap the underlying storage.
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S, Type> StorageInspect<Type> for StructuredStorage<S>
+where
+    S: KeyValueStore,
+    S::Error: From<DecodeError>,
+    Type: Table + TableWithCodec,
+{
+    type Error = S::Error;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        let key = Type::KeyCodec::encode(key);
+        match self.storage.get(Type::COLUMN, &key)? {
+            Some(value) => Ok(Some(Cow::Owned(Type::ValueCodec::decode(&value)?))),
+            None => Ok(None),
+        }
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        let key = Type::KeyCodec::encode(key);
+        self.storage.exists(Type::COLUMN, &key)
+    }
+}
+
+impl<S, Type> StorageMutate<Type> for StructuredStorage<S>
+where
+    S: KeyValueStore,
+    S::Error: From<DecodeError>,
+    Type: Table + TableWithCodec,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        let key = Type::KeyCodec::encode(key);
+        let value = Type::ValueCodec::encode(value);
+        match self.storage.replace(Type::COLUMN, &key, &value)? {
+            Some(previous) => Ok(Some(Type::ValueCodec::decode(&previous)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        let key = Type::KeyCodec::encode(key);
+        match self.storage.take(Type::COLUMN, &key)? {
+            Some(previous) => Ok(Some(Type::ValueCodec::decode(&previous)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+// The values are written to the store without fetching the previous values.
+impl<S, Type> StorageWrite<Type> for StructuredStorage<S>
+where
+    S: KeyValueStore,
+    S::Error: From<DecodeError>,
+    Type: Table + TableWithCodec,
+{
+    fn write(&mut self, key: &Type::Key, value: &Type::SetValue) -> Result<(), Self::Error> {
+        self.storage.put(
+            Type::COLUMN,
+            &Type::KeyCodec::encode(key),
+            &Type::ValueCodec::encode(value),
+        )
+    }
+
+    fn delete(&mut self, key: &Type::Key) -> Result<(), Self::Error> {
+        self.storage
+            .delete(Type::COLUMN, &Type::KeyCodec::encode(key))
+    }
+}
+
+impl<S, Type> StorageBatchMutate<Type> for StructuredStorage<S>
+where
+    S: KeyValueStore,
+    S::Error: From<DecodeError>,
+    Type: Table + TableWithCodec,
+{
+    fn insert_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = (&'a Type::Key, &'a Type::SetValue)>,
+        Type::Key: 'a,
+        Type::SetValue: 'a,
+    {
+        for (key, value) in set {
+            StorageWrite::<Type>::write(self, key, value)?;
+        }
+        Ok(())
+    }
+
+    fn remove_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = &'a Type::Key>,
+        Type::Key: 'a,
+    {
+        for key in set {
+            StorageWrite::<Type>::delete(self, key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterates over the column of the table in the lexicographic order of the encoded keys.
+///
+/// The order matches the order of keys only if the key codec preserves it, like
+/// [`BigEndian`](crate::codec::BigEndian) does. With other codecs, such as
+/// [`Compact`](crate::codec::Compact), the iteration and its range bounds follow the encoded bytes.
+impl<S, Type> StorageIterate<Type> for StructuredStorage<S>
+where
+    S: IterableKeyValueStore,
+    S::Error: From<DecodeError> + 'static,
+    Type: Table + TableWithCodec,
+    Type::Key: 'static,
+    Type::GetValue: 'static,
+{
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        let start = start.map(Type::KeyCodec::encode);
+        let end = end.map(Type::KeyCodec::encode);
+        let iter = self.storage.iter_column(
+            Type::COLUMN,
+            start.as_ref().map(AsRef::as_ref),
+            end.as_ref().map(AsRef::as_ref),
+            direction,
+        );
+        Box::new(iter.map(|item| {
+            let (key, value) = item?;
+            Ok((
+                Type::KeyCodec::decode(&key)?,
+                Type::ValueCodec::decode(&value)?,
+            ))
+        }))
+    }
+}
@@ -0,0 +1,131 @@
+use crate::Mappable;
+
+/// The [`Mappable`] that is stored under its own column of a
+/// [`KeyValueStore`](crate::KeyValueStore).
+///
+/// The column should be stable: changing it makes the values stored before unreachable. Columns of
+/// tables stored in the same database should be unique, which can be checked at compile time with
+/// [`assert_unique_columns!`](crate::assert_unique_columns).
+pub trait Table: Mappable {
+    /// The identifier of the column of the table.
+    const COLUMN: u32;
+    /// The human-readable name of the table.
+    const NAME: &'static str;
+}
+
+/// The column and the name of the [`Table`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TableInfo {
+    /// The [`Table::COLUMN`].
+    pub column: u32,
+    /// The [`Table::NAME`].
+    pub name: &'static str,
+}
+
+impl TableInfo {
+    /// Return the information about the `T`.
+    pub const fn of<T: Table>() -> Self {
+        Self {
+            column: T::COLUMN,
+            name: T::NAME,
+        }
+    }
+}
+
+/// Return the first pair of `tables` that share the same column, or `None` if all columns are
+/// unique.
+pub const fn find_duplicate_column(tables: &[TableInfo]) -> Option<(TableInfo, TableInfo)> {
+    let mut i = 0;
+    while i < tables.len() {
+        let mut j = i + 1;
+        while j < tables.len() {
+            if tables[i].column == tables[j].column {
+                return Some((tables[i], tables[j]));
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Fail the compilation if any two of the listed [`Table`]s share the same column.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{assert_unique_columns, Mappable, Table};
+///
+/// pub struct Contracts;
+///
+/// impl Mappable for Contracts {
+///     type Key = [u8; 32];
+///     type SetValue = [u8];
+///     type GetValue = Vec<u8>;
+/// }
+///
+/// impl Table for Contracts {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "Contracts";
+/// }
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// impl Table for Balances {
+///     const COLUMN: u32 = 1;
+///     const NAME: &'static str = "Balances";
+/// }
+///
+/// assert_unique_columns!(Contracts, Balances);
+/// ```
+///
+/// ```compile_fail
+/// use fuel_storage::{assert_unique_columns, Mappable, Table};
+///
+/// pub struct Contracts;
+///
+/// impl Mappable for Contracts {
+///     type Key = [u8; 32];
+///     type SetValue = [u8];
+///     type GetValue = Vec<u8>;
+/// }
+///
+/// impl Table for Contracts {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "Contracts";
+/// }
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// impl Table for Balances {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "Balances";
+/// }
+///
+/// assert_unique_columns!(Contracts, Balances);
+/// ```
+#[macro_export]
+macro_rules! assert_unique_columns {
+    ($($table:ty),+ $(,)?) => {
+        const _: () = {
+            let tables = [$($crate::TableInfo::of::<$table>()),+];
+            if let Some((_, duplicate)) = $crate::find_duplicate_column(&tables) {
+                // Const panics can only format a single string, so report the name of the
+                // duplicate.
+                panic!("{}", duplicate.name);
+            }
+        };
+    };
+}
@@ -0,0 +1,450 @@
+use crate::{
+    borrow, iter, BoxedIter, IterDirection, KVItem, Mappable, StorageBatchMutate, StorageInspect,
+    StorageIterate, StorageMutate, StorageWrite,
+};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+    vec,
+    vec::Vec,
+};
+use core::{
+    any::{Any, TypeId},
+    ops::Bound,
+};
+
+/// The overlay of one table: `Some(value)` for inserted values and `None` for removed ones.
+type Overlay<Type> = BTreeMap<<Type as Mappable>::Key, Option<<Type as Mappable>::GetValue>>;
+
+/// Applies the type-erased overlay of one table to the storage, returning the type-erased error of
+/// the table.
+type Applier<S> = fn(&mut S, Box<dyn Any>) -> Result<(), Box<dyn Any>>;
+
+/// Merges the type-erased overlay of one table into the overlay of the same table below it.
+type Merger = fn(&mut Box<dyn Any>, Box<dyn Any>);
+
+/// One layer of the overlay per savepoint, tables are distinguished by the type of the `Mappable`.
+type Layer = BTreeMap<TypeId, Box<dyn Any>>;
+
+/// The type-erased operations over the overlay of one table.
+struct TableOps<S> {
+    apply: Applier<S>,
+    merge: Merger,
+    /// The type of the error of the table in the underlying storage.
+    error: TypeId,
+}
+
+/// The marker of the state of the [`StorageTransaction`] returned by
+/// [`StorageTransaction::savepoint`]. It is consumed by [`StorageTransaction::rollback_to`] or
+/// [`StorageTransaction::release`].
+///
+/// Every savepoint has its own id, unique among the savepoints of its transaction, so a discarded
+/// savepoint is never taken for an existing one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Savepoint(usize);
+
+/// The transactional wrapper around the storage. All inserts and removes are buffered in the
+/// overlay and applied to the underlying storage only on [`StorageTransaction::commit`]. Reads are
+/// served from the overlay first and fall back to the underlying storage.
+///
+/// Dropping the transaction or calling [`StorageTransaction::rollback`] discards all changes.
+///
+/// The changes made after a [`Savepoint`] can be undone with [`StorageTransaction::rollback_to`]
+/// without touching the changes made before it. Savepoints can be nested and should be rolled back
+/// or released in the reverse order of their creation.
+///
+/// Every table reports the error of the same table of the underlying storage. The
+/// [`StorageTransaction::commit`] reports the error shared by all written tables.
+///
+/// # Example
+///
+/// ```rust
+/// use core::convert::Infallible;
+/// use fuel_storage::{Mappable, MemoryStorage, StorageAsMut, StorageTransaction};
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// let mut storage = MemoryStorage::new();
+/// storage.storage::<Balances>().insert(&1, &100).unwrap();
+///
+/// let mut transaction = StorageTransaction::new(&mut storage);
+/// assert_eq!(transaction.storage::<Balances>().insert(&1, &50), Ok(Some(100)));
+/// assert_eq!(transaction.storage::<Balances>().remove(&1), Ok(Some(50)));
+/// transaction.rollback();
+/// assert_eq!(storage.storage::<Balances>().get(&1).unwrap().unwrap().into_owned(), 100);
+///
+/// let mut transaction = StorageTransaction::new(&mut storage);
+/// transaction.storage::<Balances>().insert(&2, &200).unwrap();
+///
+/// let savepoint = transaction.savepoint();
+/// transaction.storage::<Balances>().insert(&2, &300).unwrap();
+/// transaction.storage::<Balances>().remove(&1).unwrap();
+/// transaction.rollback_to(savepoint);
+///
+/// assert_eq!(transaction.storage::<Balances>().get(&2).unwrap().unwrap().into_owned(), 200);
+/// assert!(transaction.storage::<Balances>().contains_key(&1).unwrap());
+///
+/// let committed: Result<_, Infallible> = transaction.commit();
+/// committed.unwrap();
+/// assert_eq!(storage.storage::<Balances>().get(&2).unwrap().unwrap().into_owned(), 200);
+/// ```
+pub struct StorageTransaction<S> {
+    storage: S,
+    layers: Vec<Layer>,
+    /// The ids of the savepoints of the layers above the base one.
+    savepoints: Vec<usize>,
+    /// The id of the next savepoint.
+    next_savepoint: usize,
+    tables: BTreeMap<TypeId, TableOps<S>>,
+}
+
+impl<S> StorageTransaction<S> {
+    /// Start a new transaction on top of the `storage`.
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            layers: vec![Layer::new()],
+            savepoints: Vec::new(),
+            next_savepoint: 0,
+            tables: Default::default(),
+        }
+    }
+
+    /// Return the underlying storage. Its state doesn't include the changes of the transaction.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    /// Return `true` if the transaction doesn't contain any changes.
+    pub fn is_empty(&self) -> bool {
+        self.layers.iter().all(Layer::is_empty)
+    }
+
+    /// Create a savepoint. The changes made after it can be undone with
+    /// [`StorageTransaction::rollback_to`] or kept with [`StorageTransaction::release`].
+    pub fn savepoint(&mut self) -> Savepoint {
+        let id = self.next_savepoint;
+        self.next_savepoint += 1;
+        self.layers.push(Layer::new());
+        self.savepoints.push(id);
+        Savepoint(id)
+    }
+
+    /// Discard all changes made after the `savepoint`, including the changes made after the
+    /// savepoints created after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `savepoint` was already discarded by rolling back or releasing an older one.
+    pub fn rollback_to(&mut self, savepoint: Savepoint) {
+        let depth = self.depth(&savepoint);
+        self.layers.truncate(depth);
+        self.savepoints.truncate(depth - 1);
+    }
+
+    /// Remove the `savepoint` and keep the changes made after it as a part of the enclosing
+    /// savepoint or the transaction itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `savepoint` was already discarded by rolling back or releasing an older one.
+    pub fn release(&mut self, savepoint: Savepoint) {
+        let depth = self.depth(&savepoint);
+        self.merge_down_to(depth - 1);
+    }
+
+    /// Apply all changes to the underlying storage and return it.
+    ///
+    /// The `E` is the error of the tables of the underlying storage written through the
+    /// transaction, which all of them should share. The changes are applied table by table. If the
+    /// underlying storage fails, the error is returned and the storage may contain a part of the
+    /// changes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the error of a written table isn't the `E`, before applying any changes.
+    pub fn commit<E: 'static>(mut self) -> Result<S, E> {
+        self.merge_down_to(0);
+        let changes = self.layers.pop().expect("The base layer always exists");
+        assert!(
+            changes
+                .keys()
+                .all(|type_id| self.tables[type_id].error == TypeId::of::<E>()),
+            "The error of a table of the transaction differs from the error of the commit"
+        );
+        for (type_id, changes) in changes {
+            let apply = self.tables[&type_id].apply;
+            apply(&mut self.storage, changes).map_err(|error| {
+                *error
+                    .downcast()
+                    .expect("The errors of the tables are checked before applying")
+            })?;
+        }
+        Ok(self.storage)
+    }
+
+    /// Discard all changes and return the underlying storage.
+    pub fn rollback(self) -> S {
+        self.storage
+    }
+
+    /// Return the depth of the layer created by the `savepoint`.
+    fn depth(&self, savepoint: &Savepoint) -> usize {
+        let index = self
+            .savepoints
+            .iter()
+            .position(|id| *id == savepoint.0)
+            .expect("The savepoint was rolled back or released");
+        index + 1
+    }
+
+    /// Merge all layers above the `depth` into the layer at the `depth`.
+    fn merge_down_to(&mut self, depth: usize) {
+        self.savepoints.truncate(depth);
+        for layer in self.layers.split_off(depth + 1) {
+            for (type_id, changes) in layer {
+                match self.layers[depth].get_mut(&type_id) {
+                    Some(below) => (self.tables[&type_id].merge)(below, changes),
+                    None => {
+                        self.layers[depth].insert(type_id, changes);
+                    }
+                }
+            }
+        }
+    }
+
+    fn overlays<Type>(&self) -> impl Iterator<Item = &Overlay<Type>>
+    where
+        Type: Mappable + 'static,
+        Type::Key: 'static,
+        Type::GetValue: 'static,
+    {
+        self.layers.iter().rev().filter_map(|layer| {
+            layer
+                .get(&TypeId::of::<Type>())
+                .and_then(|overlay| overlay.downcast_ref())
+        })
+    }
+
+    /// Return the latest change of the `key` from the overlay.
+    fn change<Type>(&self, key: &Type::Key) -> Option<&Option<Type::GetValue>>
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + 'static,
+        Type::GetValue: 'static,
+    {
+        self.overlays::<Type>().find_map(|overlay| overlay.get(key))
+    }
+
+    fn overlay_mut<Type>(&mut self) -> &mut Overlay<Type>
+    where
+        Type: Mappable + 'static,
+        Type::Key: Ord + Clone + 'static,
+        Type::SetValue: ToOwned<Owned = Type::GetValue>,
+        Type::GetValue: 'static,
+        S: StorageMutate<Type>,
+        S::Error: 'static,
+    {
+        self.tables.entry(TypeId::of::<Type>()).or_insert(TableOps {
+            apply: apply::<Type, S>,
+            merge: merge::<Type>,
+            error: TypeId::of::<S::Error>(),
+        });
+        self.layers
+            .last_mut()
+            .expect("The base layer always exists")
+            .entry(TypeId::of::<Type>())
+            .or_insert_with(|| Box::new(Overlay::<Type>::new()))
+            .downcast_mut()
+            .expect("The overlay is always created with the type of the `Mappable`")
+    }
+}
+
+fn merge<Type>(below: &mut Box<dyn Any>, above: Box<dyn Any>)
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + 'static,
+    Type::GetValue: 'static,
+{
+    let below = below
+        .downcast_mut::<Overlay<Type>>()
+        .expect("The merger is always registered with the type of the overlay");
+    let above = above
+        .downcast::<Overlay<Type>>()
+        .expect("The merger is always registered with the type of the overlay");
+    below.extend(*above);
+}
+
+fn apply<Type, S>(storage: &mut S, changes: Box<dyn Any>) -> Result<(), Box<dyn Any>>
+where
+    Type: Mappable + 'static,
+    Type::Key: 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: 'static,
+    S: StorageMutate<Type>,
+    S::Error: 'static,
+{
+    let erase = |error: S::Error| -> Box<dyn Any> { Box::new(error) };
+    let changes = changes
+        .downcast::<Overlay<Type>>()
+        .expect("The applier is always registered with the type of the overlay");
+    for (key, value) in changes.into_iter() {
+        match value {
+            Some(value) => storage
+                .insert(&key, borrow::<Type::SetValue>(&value))
+                .map_err(erase)?,
+            None => storage.remove(&key).map_err(erase)?,
+        };
+    }
+    Ok(())
+}
+
+impl<Type, S> StorageInspect<Type> for StorageTransaction<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + 'static,
+    Type::GetValue: 'static,
+    S: StorageInspect<Type>,
+{
+    type Error = S::Error;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        match self.change::<Type>(key) {
+            Some(value) => Ok(value.as_ref().map(Cow::Borrowed)),
+            None => self.storage.get(key),
+        }
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        match self.change::<Type>(key) {
+            Some(value) => Ok(value.is_some()),
+            None => self.storage.contains_key(key),
+        }
+    }
+}
+
+impl<Type, S> StorageMutate<Type> for StorageTransaction<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: 'static,
+    S: StorageMutate<Type>,
+    S::Error: 'static,
+{
+    fn insert(
+        &mut self,
+        key: &Type::Key,
+        value: &Type::SetValue,
+    ) -> Result<Option<Type::GetValue>, Self::Error> {
+        let previous = StorageInspect::<Type>::get(self, key)?.map(Cow::into_owned);
+        self.overlay_mut::<Type>()
+            .insert(key.clone(), Some(value.to_owned()));
+        Ok(previous)
+    }
+
+    fn remove(&mut self, key: &Type::Key) -> Result<Option<Type::GetValue>, Self::Error> {
+        let previous = StorageInspect::<Type>::get(self, key)?.map(Cow::into_owned);
+        if previous.is_some() {
+            self.overlay_mut::<Type>().insert(key.clone(), None);
+        }
+        Ok(previous)
+    }
+}
+
+// The values are written to the overlay without fetching the previous values.
+impl<Type, S> StorageWrite<Type> for StorageTransaction<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: 'static,
+    S: StorageMutate<Type>,
+    S::Error: 'static,
+{
+    fn write(&mut self, key: &Type::Key, value: &Type::SetValue) -> Result<(), Self::Error> {
+        self.overlay_mut::<Type>()
+            .insert(key.clone(), Some(value.to_owned()));
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &Type::Key) -> Result<(), Self::Error> {
+        self.overlay_mut::<Type>().insert(key.clone(), None);
+        Ok(())
+    }
+}
+
+// The batches are written to the overlay without fetching the previous values.
+impl<Type, S> StorageBatchMutate<Type> for StorageTransaction<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::SetValue: ToOwned<Owned = Type::GetValue>,
+    Type::GetValue: 'static,
+    S: StorageMutate<Type>,
+    S::Error: 'static,
+{
+    fn insert_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = (&'a Type::Key, &'a Type::SetValue)>,
+        Type::Key: 'a,
+        Type::SetValue: 'a,
+    {
+        self.overlay_mut::<Type>().extend(
+            set.into_iter()
+                .map(|(key, value)| (key.clone(), Some(value.to_owned()))),
+        );
+        Ok(())
+    }
+
+    fn remove_batch<'a, Iter>(&mut self, set: Iter) -> Result<(), Self::Error>
+    where
+        Iter: IntoIterator<Item = &'a Type::Key>,
+        Type::Key: 'a,
+    {
+        self.overlay_mut::<Type>()
+            .extend(set.into_iter().map(|key| (key.clone(), None)));
+        Ok(())
+    }
+}
+
+impl<Type, S> StorageIterate<Type> for StorageTransaction<S>
+where
+    Type: Mappable + 'static,
+    Type::Key: Ord + Clone + 'static,
+    Type::GetValue: 'static,
+    S: StorageIterate<Type>,
+    S::Error: 'static,
+{
+    fn iter_range(
+        &self,
+        start: Bound<&Type::Key>,
+        end: Bound<&Type::Key>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Type, Self::Error>> {
+        // The upper layers override the lower ones.
+        let mut changes = BTreeMap::new();
+        for overlay in self
+            .overlays::<Type>()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+        {
+            changes.extend(iter::btree_range(overlay, start, end).into_iter().flatten());
+        }
+        let changes = match direction {
+            IterDirection::Forward => changes.into_iter().collect(),
+            IterDirection::Reverse => changes.into_iter().rev().collect(),
+        };
+
+        let inner = self.storage.iter_range(start, end, direction);
+        Box::new(iter::MergedIter::new(inner, changes, direction))
+    }
+}
@@ -0,0 +1,565 @@
+use crate::{
+    codec::{Compact, Decode, DecodeError, Encode},
+    kv_store::{IterableKeyValueStore, KeyValueStore},
+    Changes, IterDirection, KeyValueOverlay, StorageInspect, Table, TableWithCodec,
+};
+use alloc::{borrow::Cow, vec::Vec};
+use core::{fmt, ops::Bound};
+
+/// The error of the [`VersionedStorage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VersionError {
+    /// The committed height isn't above the latest committed height.
+    NonIncreasingHeight { height: u64, latest: u64 },
+    /// The state at the height isn't retained: it is pruned or not committed yet.
+    Unretained(u64),
+    /// The changes write to the column reserved for the history of versions.
+    ReservedColumn(u32),
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonIncreasingHeight { height, latest } => write!(
+                f,
+                "the height {height} isn't above the latest committed height {latest}"
+            ),
+            Self::Unretained(height) => {
+                write!(f, "the state at the height {height} isn't retained")
+            }
+            Self::ReservedColumn(column) => {
+                write!(
+                    f,
+                    "the column {column} is reserved for the history of versions"
+                )
+            }
+        }
+    }
+}
+
+/// The wrapper around the [`IterableKeyValueStore`] that commits [`Changes`] labelled with
+/// increasing heights and serves reads of the state at any retained height with the
+/// [`storage_at`](Self::storage_at).
+///
+/// The underlying store contains the latest state, so reads at the latest height cost the same as
+/// without the wrapper. Every commit also stores the values replaced by the changes, so the value
+/// at an older height is the value replaced by the first change after that height. The history is
+/// kept in the [`HISTORY_COLUMN`](Self::HISTORY_COLUMN), the
+/// [`VERSIONS_COLUMN`](Self::VERSIONS_COLUMN) and the
+/// [`CHECKPOINTS_COLUMN`](Self::CHECKPOINTS_COLUMN), which tables should not use. Each commit,
+/// together with the pruning after it, is written with one
+/// [`apply`](crate::KeyValueStore::apply), so it is atomic if the store applies changes
+/// atomically.
+///
+/// The history grows with every commit unless the heights are pruned, either explicitly with the
+/// [`prune`](Self::prune) or after every commit according to the [`PruningPolicy`].
+///
+/// The error of the store should be convertible from the [`VersionError`] and the
+/// [`DecodeError`].
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{
+///     codec::Compact, Changes, Mappable, MemoryKeyValueStore, StorageInspect, Table,
+///     TableWithCodec, VersionedStorage,
+/// };
+///
+/// pub struct Balances;
+///
+/// impl Mappable for Balances {
+///     type Key = u128;
+///     type SetValue = u64;
+///     type GetValue = u64;
+/// }
+///
+/// impl TableWithCodec for Balances {
+///     type KeyCodec = Compact;
+///     type ValueCodec = Compact;
+/// }
+///
+/// impl Table for Balances {
+///     const COLUMN: u32 = 0;
+///     const NAME: &'static str = "Balances";
+/// }
+///
+/// let mut storage = VersionedStorage::new(MemoryKeyValueStore::new());
+/// for (height, balance) in [(1, 100), (2, 50), (3, 70)] {
+///     let mut block = Changes::new();
+///     block.insert::<Balances>(&1, &balance);
+///     storage.commit(height, &block).unwrap();
+/// }
+///
+/// let balance = |height| {
+///     let view = storage.storage_at(height).unwrap();
+///     StorageInspect::<Balances>::get(&view, &1).unwrap().unwrap().into_owned()
+/// };
+/// assert_eq!(balance(1), 100);
+/// assert_eq!(balance(2), 50);
+/// assert_eq!(balance(3), 70);
+/// assert!(storage.storage_at(4).is_err());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct VersionedStorage<S> {
+    store: S,
+    policy: PruningPolicy,
+}
+
+/// The heights retained by the [`VersionedStorage`] after commits and pruning.
+///
+/// # Example
+///
+/// ```rust
+/// use fuel_storage::{Changes, MemoryKeyValueStore, PruningPolicy, VersionedStorage};
+///
+/// let mut storage =
+///     VersionedStorage::with_policy(MemoryKeyValueStore::new(), PruningPolicy::Checkpoints(10));
+/// for height in 1..=25u64 {
+///     let mut block = Changes::new();
+///     block.put(0, b"height", &height.to_be_bytes());
+///     storage.commit(height, &block).unwrap();
+/// }
+///
+/// let retained: Vec<_> = (1..=25).filter(|h| storage.is_retained(*h).unwrap()).collect();
+/// assert_eq!(retained, [10, 20, 25]);
+/// let view = storage.storage_at(10).unwrap();
+/// assert_eq!(view.get(0, b"height").unwrap(), Some(10u64.to_be_bytes().to_vec()));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PruningPolicy {
+    /// Keep every committed height.
+    #[default]
+    Archive,
+    /// Keep the last `N` heights, including the latest one.
+    KeepLast(u64),
+    /// Keep the heights that are multiples of `K`, besides the latest height.
+    Checkpoints(u64),
+}
+
+impl PruningPolicy {
+    /// Return the interval of the checkpoints kept below the pruned height, `0` if there are none.
+    fn checkpoint_interval(&self) -> u64 {
+        match self {
+            Self::Checkpoints(interval) => (*interval).max(1),
+            Self::Archive | Self::KeepLast(_) => 0,
+        }
+    }
+
+    /// Return `true` if the `height` below the pruned height is kept as a checkpoint.
+    fn is_checkpoint(&self, height: u64) -> bool {
+        let interval = self.checkpoint_interval();
+        interval != 0 && height.is_multiple_of(interval)
+    }
+
+    /// Return the height to prune up to after the commit of the `latest` height.
+    fn prune_height(&self, latest: u64) -> Option<u64> {
+        match self {
+            Self::Archive => None,
+            Self::KeepLast(count) => Some(latest.saturating_sub((*count).max(1) - 1)),
+            Self::Checkpoints(_) => Some(latest),
+        }
+    }
+}
+
+/// The retained heights: the contiguous range of heights from the `earliest` to the `latest` and
+/// the checkpoints below it, stored one per key. The `interval` is the checkpoint interval of the
+/// policy that kept the checkpoints, so they are revisited only when the policy changes.
+#[derive(Debug, Clone, Copy)]
+struct Versions {
+    interval: u64,
+    earliest: u64,
+    latest: u64,
+}
+
+impl Versions {
+    fn contains<T: KeyValueStore>(&self, store: &T, height: u64) -> Result<bool, T::Error> {
+        if (self.earliest..=self.latest).contains(&height) {
+            return Ok(true);
+        }
+        store.exists(
+            VersionedStorage::<T>::CHECKPOINTS_COLUMN,
+            &height.to_be_bytes(),
+        )
+    }
+
+    /// Return `true` if any of the heights from the `start` to the `end` is retained.
+    fn intersects<T: IterableKeyValueStore>(
+        &self,
+        store: &T,
+        start: u64,
+        end: u64,
+    ) -> Result<bool, T::Error> {
+        if end >= self.earliest && start <= self.latest {
+            return Ok(true);
+        }
+        let mut checkpoints = store.iter_column(
+            VersionedStorage::<T>::CHECKPOINTS_COLUMN,
+            Bound::Included(&start.to_be_bytes()),
+            Bound::Included(&end.to_be_bytes()),
+            IterDirection::Forward,
+        );
+        Ok(checkpoints.next().transpose()?.is_some())
+    }
+}
+
+impl<S> VersionedStorage<S> {
+    /// The column with the values replaced by the commits, keyed by the column and the key of
+    /// the value and the height of the commit.
+    pub const HISTORY_COLUMN: u32 = u32::MAX;
+    /// The column with the values replaced by the commits as the [`Changes`], keyed by the
+    /// height, and the range of retained heights under the empty key.
+    pub const VERSIONS_COLUMN: u32 = u32::MAX - 1;
+    /// The column with the checkpoints retained below the range of retained heights, keyed by
+    /// the height.
+    pub const CHECKPOINTS_COLUMN: u32 = u32::MAX - 2;
+
+    /// Wrap the `store` keeping every height. The history of a previously wrapped store is
+    /// preserved.
+    pub fn new(store: S) -> Self {
+        Self::with_policy(store, PruningPolicy::Archive)
+    }
+
+    /// Wrap the `store` pruning the heights not retained by the `policy` after every commit.
+    pub fn with_policy(store: S, policy: PruningPolicy) -> Self {
+        Self { store, policy }
+    }
+
+    /// Return the pruning policy.
+    pub fn policy(&self) -> PruningPolicy {
+        self.policy
+    }
+
+    /// Return the underlying store. Its state is the state at the latest height.
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Unwrap the underlying store.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+impl<S> VersionedStorage<S>
+where
+    S: IterableKeyValueStore,
+    S::Error: From<VersionError> + From<DecodeError>,
+{
+    /// Return the lowest and the highest retained heights, or `None` if nothing was committed.
+    /// Heights between them may be pruned if they aren't checkpoints.
+    pub fn heights(&self) -> Result<Option<(u64, u64)>, S::Error> {
+        let Some(versions) = Self::versions_in(&self.store)? else {
+            return Ok(None);
+        };
+        let first_checkpoint = self
+            .store
+            .iter_column(
+                Self::CHECKPOINTS_COLUMN,
+                Bound::Unbounded,
+                Bound::Unbounded,
+                IterDirection::Forward,
+            )
+            .next()
+            .transpose()?;
+        let lowest = match first_checkpoint {
+            Some((height, _)) => decode_height(&height)?,
+            None => versions.earliest,
+        };
+        Ok(Some((lowest, versions.latest)))
+    }
+
+    /// Return `true` if the state at the `height` is retained.
+    pub fn is_retained(&self, height: u64) -> Result<bool, S::Error> {
+        match Self::versions_in(&self.store)? {
+            Some(versions) => versions.contains(&self.store, height),
+            None => Ok(false),
+        }
+    }
+
+    /// Apply the `changes` as the state at the `height`, which should be above the latest
+    /// committed height, and return their inverse changes. The heights not retained by the
+    /// policy are pruned in the same [`apply`](crate::KeyValueStore::apply).
+    pub fn commit(&mut self, height: u64, changes: &Changes) -> Result<Changes, S::Error> {
+        let mut overlay = KeyValueOverlay::new(&mut self.store);
+        let inverse = Self::commit_in(&mut overlay, height, changes)?;
+        if let Some(prune_height) = self.policy.prune_height(height) {
+            Self::prune_in(&mut overlay, self.policy, prune_height)?;
+        }
+        overlay.commit()?;
+        Ok(inverse)
+    }
+
+    /// Prune the heights below the `height`, except the checkpoints of the policy, reclaiming
+    /// the replaced values no retained height reads, including the replaced roots of Merkle trees.
+    /// The nodes of sparse Merkle trees are stored under their hashes and may be shared by several
+    /// trees, so they are never replaced and stay in the store. The latest height is never pruned.
+    ///
+    /// The pruning is written with one [`apply`](crate::KeyValueStore::apply).
+    pub fn prune(&mut self, height: u64) -> Result<(), S::Error> {
+        let mut overlay = KeyValueOverlay::new(&mut self.store);
+        Self::prune_in(&mut overlay, self.policy, height)?;
+        overlay.commit()?;
+        Ok(())
+    }
+
+    /// Return the read-only view of the state at the retained `height`.
+    pub fn storage_at(&self, height: u64) -> Result<StorageAt<'_, S>, S::Error> {
+        match Self::versions_in(&self.store)? {
+            Some(versions) if versions.contains(&self.store, height)? => Ok(StorageAt {
+                store: &self.store,
+                height,
+                latest: versions.latest,
+            }),
+            _ => Err(VersionError::Unretained(height).into()),
+        }
+    }
+
+    /// Write the `changes` as the state at the `height` to the `store`, with the history of the
+    /// replaced values.
+    fn commit_in<T>(store: &mut T, height: u64, changes: &Changes) -> Result<Changes, S::Error>
+    where
+        T: KeyValueStore<Error = S::Error>,
+    {
+        let versions = match Self::versions_in(store)? {
+            Some(versions) if height <= versions.latest => {
+                return Err(VersionError::NonIncreasingHeight {
+                    height,
+                    latest: versions.latest,
+                }
+                .into())
+            }
+            Some(versions) => Versions {
+                latest: height,
+                ..versions
+            },
+            None => Versions {
+                interval: 0,
+                earliest: height,
+                latest: height,
+            },
+        };
+
+        let mut inverse = Changes::new();
+        for (column, key, _) in changes.iter() {
+            if [
+                Self::HISTORY_COLUMN,
+                Self::VERSIONS_COLUMN,
+                Self::CHECKPOINTS_COLUMN,
+            ]
+            .contains(&column)
+            {
+                return Err(VersionError::ReservedColumn(column).into());
+            }
+            let previous = store.get(column, key)?;
+            match &previous {
+                Some(previous) => inverse.put(column, key, previous),
+                None => inverse.delete(column, key),
+            }
+            store.put(
+                Self::HISTORY_COLUMN,
+                &history_key(column, key, height),
+                &Compact::encode(&previous),
+            )?;
+        }
+        store.put(
+            Self::VERSIONS_COLUMN,
+            &height.to_be_bytes(),
+            &inverse.encode(),
+        )?;
+        Self::put_versions(store, &versions)?;
+        store.apply(changes)?;
+        Ok(inverse)
+    }
+
+    /// Prune the heights below the `height` in the `store`, keeping the checkpoints of the
+    /// `policy`.
+    fn prune_in<T>(store: &mut T, policy: PruningPolicy, height: u64) -> Result<(), S::Error>
+    where
+        T: IterableKeyValueStore<Error = S::Error>,
+    {
+        let Some(versions) = Self::versions_in(store)? else {
+            return Ok(());
+        };
+        let earliest = height.clamp(versions.earliest, versions.latest);
+        let interval = policy.checkpoint_interval();
+
+        // Values replaced below the earliest height are read only at checkpoints, so they need
+        // to be revisited only if some of the checkpoints are dropped.
+        let mut start = versions.earliest;
+        if versions.interval != interval {
+            let checkpoints = store
+                .iter_column(
+                    Self::CHECKPOINTS_COLUMN,
+                    Bound::Unbounded,
+                    Bound::Unbounded,
+                    IterDirection::Forward,
+                )
+                .map(|item| item.map(|(height, _)| height))
+                .collect::<Result<Vec<_>, _>>()?;
+            for checkpoint in checkpoints {
+                if !policy.is_checkpoint(decode_height(&checkpoint)?) {
+                    store.delete(Self::CHECKPOINTS_COLUMN, &checkpoint)?;
+                    start = 0;
+                }
+            }
+        }
+        for checkpoint in (versions.earliest..earliest).filter(|h| policy.is_checkpoint(*h)) {
+            store.put(Self::CHECKPOINTS_COLUMN, &checkpoint.to_be_bytes(), &[])?;
+        }
+        let pruned = Versions {
+            interval,
+            earliest,
+            latest: versions.latest,
+        };
+        Self::put_versions(store, &pruned)?;
+
+        // The value replaced at the height `g` is the value at the heights from the previous
+        // change of the key to `g - 1`, so it is obsolete if none of them is retained. Values
+        // replaced above the earliest height are always read at the height before.
+        let mut batch = Changes::new();
+        let end = earliest.to_be_bytes();
+        let replaced = store.iter_column(
+            Self::VERSIONS_COLUMN,
+            Bound::Included(&start.to_be_bytes()),
+            Bound::Included(&end),
+            IterDirection::Forward,
+        );
+        for item in replaced {
+            let (height_key, replaced) = item?;
+            let changed_at = decode_height(&height_key)?;
+            let replaced = Changes::decode(&replaced)?;
+            let mut retained = Changes::new();
+            for (column, key, value) in replaced.iter() {
+                let previous_change = store
+                    .iter_column(
+                        Self::HISTORY_COLUMN,
+                        Bound::Included(&history_key(column, key, 0)),
+                        Bound::Excluded(&history_key(column, key, changed_at)),
+                        IterDirection::Reverse,
+                    )
+                    .next()
+                    .transpose()?
+                    .map(|(history_key, _)| decode_height(&history_key[history_key.len() - 8..]))
+                    .transpose()?;
+                let read_from = previous_change.unwrap_or(0);
+                if changed_at > 0 && pruned.intersects(store, read_from, changed_at - 1)? {
+                    match value {
+                        Some(value) => retained.put(column, key, value),
+                        None => retained.delete(column, key),
+                    }
+                } else {
+                    batch.delete(Self::HISTORY_COLUMN, &history_key(column, key, changed_at));
+                }
+            }
+            if retained.is_empty() {
+                batch.delete(Self::VERSIONS_COLUMN, &height_key);
+            } else if retained.len() < replaced.len() {
+                batch.put(Self::VERSIONS_COLUMN, &height_key, &retained.encode());
+            }
+        }
+        store.apply(&batch)
+    }
+
+    fn versions_in<T>(store: &T) -> Result<Option<Versions>, S::Error>
+    where
+        T: KeyValueStore<Error = S::Error>,
+    {
+        let Some(versions) = store.get(Self::VERSIONS_COLUMN, &[])? else {
+            return Ok(None);
+        };
+        let (interval, earliest, latest) = Compact::decode(&versions)?;
+        Ok(Some(Versions {
+            interval,
+            earliest,
+            latest,
+        }))
+    }
+
+    fn put_versions<T>(store: &mut T, versions: &Versions) -> Result<(), S::Error>
+    where
+        T: KeyValueStore<Error = S::Error>,
+    {
+        let versions = (versions.interval, versions.earliest, versions.latest);
+        store.put(Self::VERSIONS_COLUMN, &[], &Compact::encode(&versions))
+    }
+}
+
+fn decode_height(bytes: &[u8]) -> Result<u64, DecodeError> {
+    let bytes = bytes.try_into().map_err(|_| DecodeError::InvalidLength)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Return the key of the history of the `key` in the `column` changed at the `height`. The length
+/// of the key goes before it, so the histories of keys that are prefixes of other keys don't mix.
+fn history_key(column: u32, key: &[u8], height: u64) -> Vec<u8> {
+    let mut history_key = Vec::with_capacity(20 + key.len());
+    history_key.extend_from_slice(&column.to_be_bytes());
+    history_key.extend_from_slice(&(key.len() as u64).to_be_bytes());
+    history_key.extend_from_slice(key);
+    history_key.extend_from_slice(&height.to_be_bytes());
+    history_key
+}
+
+/// The read-only view of the state of the [`VersionedStorage`] at one height. It serves every
+/// [`Table`] with the [`TableWithCodec`], like the [`StructuredStorage`](crate::StructuredStorage).
+#[derive(Debug)]
+pub struct StorageAt<'a, S> {
+    store: &'a S,
+    height: u64,
+    latest: u64,
+}
+
+impl<S: IterableKeyValueStore> StorageAt<'_, S> {
+    /// Return the height of the state.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Retrieve the value stored under the `key` in the `column` at the height.
+    pub fn get(&self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, S::Error>
+    where
+        S::Error: From<DecodeError>,
+    {
+        if self.height < self.latest {
+            let start = history_key(column, key, self.height + 1);
+            let end = history_key(column, key, self.latest);
+            let first = self
+                .store
+                .iter_column(
+                    VersionedStorage::<S>::HISTORY_COLUMN,
+                    Bound::Included(&start),
+                    Bound::Included(&end),
+                    IterDirection::Forward,
+                )
+                .next();
+            if let Some(item) = first {
+                let (_, previous) = item?;
+                return Ok(Compact::decode(&previous)?);
+            }
+        }
+        self.store.get(column, key)
+    }
+}
+
+impl<S, Type> StorageInspect<Type> for StorageAt<'_, S>
+where
+    S: IterableKeyValueStore,
+    S::Error: From<DecodeError>,
+    Type: Table + TableWithCodec,
+{
+    type Error = S::Error;
+
+    fn get(&self, key: &Type::Key) -> Result<Option<Cow<'_, Type::GetValue>>, Self::Error> {
+        let key = Type::KeyCodec::encode(key);
+        match StorageAt::get(self, Type::COLUMN, &key)? {
+            Some(value) => Ok(Some(Cow::Owned(Type::ValueCodec::decode(&value)?))),
+            None => Ok(None),
+        }
+    }
+
+    fn contains_key(&self, key: &Type::Key) -> Result<bool, Self::Error> {
+        let key = Type::KeyCodec::encode(key);
+        Ok(StorageAt::get(self, Type::COLUMN, &key)?.is_some())
+    }
+}
@@ -0,0 +1,171 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use fuel_storage::{
+    block_on, AsyncStorage, AsyncStorageInspect, AsyncStorageMutate, BlockingStorage, Mappable,
+    MemoryStorage, StorageAsMut,
+};
+use std::{borrow::Cow, collections::BTreeMap};
+
+pub struct Bytecode;
+
+impl Mappable for Bytecode {
+    type Key = u32;
+    type SetValue = [u8];
+    type GetValue = Vec<u8>;
+}
+
+/// The future that is pending on the first poll and ready on the next one.
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            context.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// The storage whose every operation yields once, like a storage behind the network.
+#[derive(Default)]
+struct Remote(BTreeMap<u32, Vec<u8>>);
+
+impl AsyncStorageInspect<Bytecode> for Remote {
+    type Error = ();
+
+    async fn get<'a>(&'a self, key: &u32) -> Result<Option<Cow<'a, Vec<u8>>>, ()>
+    where
+        Vec<u8>: 'a,
+    {
+        YieldOnce(false).await;
+        Ok(self.0.get(key).map(Cow::Borrowed))
+    }
+
+    async fn contains_key(&self, key: &u32) -> Result<bool, ()> {
+        YieldOnce(false).await;
+        Ok(self.0.contains_key(key))
+    }
+}
+
+impl AsyncStorageMutate<Bytecode> for Remote {
+    async fn insert(&mut self, key: &u32, value: &[u8]) -> Result<Option<Vec<u8>>, ()> {
+        YieldOnce(false).await;
+        Ok(self.0.insert(*key, value.to_vec()))
+    }
+
+    async fn remove(&mut self, key: &u32) -> Result<Option<Vec<u8>>, ()> {
+        YieldOnce(false).await;
+        Ok(self.0.remove(key))
+    }
+}
+
+#[test]
+fn blocking_storage_serves_pending_futures() {
+    let mut storage = BlockingStorage::new(Remote::default());
+    storage.storage::<Bytecode>().insert(&1, &[1, 2]).unwrap();
+    assert_eq!(
+        storage
+            .storage::<Bytecode>()
+            .get(&1)
+            .unwrap()
+            .map(Cow::into_owned),
+        Some(vec![1, 2])
+    );
+    assert!(!storage.storage::<Bytecode>().contains_key(&2).unwrap());
+    assert_eq!(
+        storage.storage::<Bytecode>().remove(&1),
+        Ok(Some(vec![1, 2]))
+    );
+}
+
+#[test]
+fn async_storage_serves_synchronous_storages() {
+    let mut storage = AsyncStorage::new(MemoryStorage::new());
+    block_on(async {
+        AsyncStorageMutate::<Bytecode>::insert(&mut storage, &3, &[3][..])
+            .await
+            .unwrap();
+        assert!(AsyncStorageInspect::<Bytecode>::contains_key(&storage, &3)
+            .await
+            .unwrap());
+        let value = AsyncStorageInspect::<Bytecode>::get(&storage, &3)
+            .await
+            .unwrap();
+        assert_eq!(value.map(Cow::into_owned), Some(vec![3]));
+    });
+
+    // The adapters compose back into the synchronous storage.
+    let mut storage = BlockingStorage::new(storage);
+    assert_eq!(storage.storage::<Bytecode>().remove(&3), Ok(Some(vec![3])));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn block_on_waits_for_wakeups_from_other_threads() {
+    use std::{
+        sync::{Arc, Mutex},
+        task::Waker,
+        thread,
+        time::Duration,
+    };
+
+    /// The state shared with the waking thread: the number of polls, the flag and the waker.
+    #[derive(Default)]
+    struct State {
+        polls: usize,
+        ready: bool,
+        waker: Option<Waker>,
+    }
+
+    /// The future that is pending until another thread sets the flag and wakes it.
+    struct Flag(Arc<Mutex<State>>);
+
+    impl Future for Flag {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+            let mut state = self.0.lock().unwrap();
+            state.polls += 1;
+            if state.ready {
+                return Poll::Ready(());
+            }
+            state.waker = Some(context.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    let state = Arc::new(Mutex::new(State::default()));
+    let setter = {
+        let state = state.clone();
+        thread::spawn(move || {
+            // The waker is registered only by the poll returning `Pending`.
+            let waker = loop {
+                if let Some(waker) = state.lock().unwrap().waker.take() {
+                    break waker;
+                }
+                thread::yield_now();
+            };
+            thread::sleep(Duration::from_millis(50));
+            state.lock().unwrap().ready = true;
+            waker.wake();
+        })
+    };
+    block_on(Flag(state.clone()));
+    setter.join().unwrap();
+
+    // The thread was parked while the flag wasn't set instead of polling the future again, which
+    // leaves room only for rare spurious wakeups of the parked thread.
+    let polls = state.lock().unwrap().polls;
+    assert!(
+        (2..5).contains(&polls),
+        "the future was polled {polls} times"
+    );
+}
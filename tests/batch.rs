@@ -0,0 +1,78 @@
+use fuel_storage::{
+    codec::{BigEndian, Raw},
+    Mappable, MemoryKeyValueStore, MemoryStorage, StorageAsMut, StorageBatchMutate,
+    StorageTransaction, StructuredStorage, Table, TableWithCodec,
+};
+use std::convert::Infallible;
+
+pub struct Balances;
+
+impl Mappable for Balances {
+    type Key = u32;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+pub struct Bytecode;
+
+impl Mappable for Bytecode {
+    type Key = u32;
+    type SetValue = [u8];
+    type GetValue = Vec<u8>;
+}
+
+impl Table for Bytecode {
+    const COLUMN: u32 = 1;
+    const NAME: &'static str = "Bytecode";
+}
+
+impl TableWithCodec for Bytecode {
+    type KeyCodec = BigEndian;
+    type ValueCodec = Raw;
+}
+
+#[test]
+fn batches_go_through_transactions() {
+    let mut storage = MemoryStorage::new();
+    storage.storage::<Balances>().insert(&1, &10).unwrap();
+
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction
+        .storage::<Balances>()
+        .insert_batch([(&2, &20), (&3, &30)])
+        .unwrap();
+    StorageBatchMutate::<Balances>::remove_batch(&mut transaction, [&1, &4]).unwrap();
+    transaction.commit::<Infallible>().unwrap();
+
+    let keys: Vec<_> = storage
+        .table::<Balances>()
+        .unwrap()
+        .keys()
+        .copied()
+        .collect();
+    assert_eq!(keys, vec![2, 3]);
+}
+
+#[test]
+fn batches_are_encoded_by_structured_storage() {
+    let mut storage = StructuredStorage::new(MemoryKeyValueStore::new());
+    let bytecode: [&[u8]; 3] = [&[1], &[2, 2], &[3, 3, 3]];
+    storage
+        .storage::<Bytecode>()
+        .insert_batch([(&1, bytecode[0]), (&2, bytecode[1]), (&3, bytecode[2])])
+        .unwrap();
+    storage.storage::<Bytecode>().remove_batch(&[2]).unwrap();
+
+    let column = storage.inner().column(Bytecode::COLUMN).unwrap();
+    let stored: Vec<_> = column
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    assert_eq!(
+        stored,
+        vec![
+            (1u32.to_be_bytes().to_vec(), vec![1]),
+            (3u32.to_be_bytes().to_vec(), vec![3, 3, 3])
+        ]
+    );
+}
@@ -0,0 +1,86 @@
+use fuel_storage::{
+    codec::{Array, BigEndian},
+    merkle::{BinaryMerkleTree, MerkleHasher, Sha256},
+    Mappable, MemoryKeyValueStore, MerkleRoot, StructuredStorage, Table, TableWithCodec,
+};
+
+pub struct ReceiptNodes;
+
+impl Mappable for ReceiptNodes {
+    type Key = u64;
+    type SetValue = MerkleRoot;
+    type GetValue = MerkleRoot;
+}
+
+impl Table for ReceiptNodes {
+    const COLUMN: u32 = 0;
+    const NAME: &'static str = "ReceiptNodes";
+}
+
+impl TableWithCodec for ReceiptNodes {
+    type KeyCodec = BigEndian;
+    type ValueCodec = Array;
+}
+
+/// The Merkle tree hash of RFC 9162, computed from scratch.
+fn reference_root(leaves: &[Vec<u8>]) -> MerkleRoot {
+    match leaves {
+        [] => Sha256::hash(&[]),
+        [leaf] => Sha256::hash(&[&[0][..], leaf].concat()),
+        _ => {
+            // The largest power of two smaller than the number of leaves.
+            let split = 1 << (usize::BITS - 1 - (leaves.len() - 1).leading_zeros());
+            let left = reference_root(&leaves[..split]);
+            let right = reference_root(&leaves[split..]);
+            Sha256::hash(&[&[1][..], &left, &right].concat())
+        }
+    }
+}
+
+fn leaf(index: u32) -> Vec<u8> {
+    index.to_le_bytes().to_vec()
+}
+
+#[test]
+fn roots_match_rfc_9162() {
+    let mut storage = StructuredStorage::new(MemoryKeyValueStore::new());
+    let mut tree = BinaryMerkleTree::<ReceiptNodes, _>::new(&mut storage);
+    let mut leaves = vec![];
+    assert_eq!(tree.root(), reference_root(&leaves));
+    for index in 0..70 {
+        tree.push(&leaf(index)).unwrap();
+        leaves.push(leaf(index));
+        assert_eq!(tree.root(), reference_root(&leaves), "{} leaves", index + 1);
+    }
+}
+
+#[test]
+fn proves_every_leaf_of_loaded_trees() {
+    let mut storage = StructuredStorage::new(MemoryKeyValueStore::new());
+    let mut tree = BinaryMerkleTree::<ReceiptNodes, _>::new(&mut storage);
+    let leaves: Vec<_> = (0..40).map(leaf).collect();
+    for leaf in &leaves {
+        tree.push(leaf).unwrap();
+    }
+
+    for count in 1..=40u64 {
+        let tree = BinaryMerkleTree::<ReceiptNodes, _>::load(&mut storage, count).unwrap();
+        let root = reference_root(&leaves[..count as usize]);
+        assert_eq!(tree.root(), root);
+        for index in 0..count {
+            let data = &leaves[index as usize];
+            let proof = tree.prove(index).unwrap().unwrap();
+            assert!(
+                proof.verify::<Sha256>(&root, data),
+                "{count} leaves, {index}"
+            );
+            assert!(!proof.verify::<Sha256>(&root, b"forged"));
+            if count > 1 {
+                let mut moved = proof.clone();
+                moved.index = (index + 1) % count;
+                assert!(!moved.verify::<Sha256>(&root, data));
+            }
+        }
+        assert!(tree.prove(count).unwrap().is_none());
+    }
+}
@@ -0,0 +1,211 @@
+mod common;
+
+use common::{path, reference_root, state_storage, ContractsState};
+use fuel_storage::{
+    merkle::{MerkleHasher, Sha256},
+    CacheCapacity, CacheStats, CachedStorage, IterDirection, Mappable, MemoryStorage, StorageAsMut,
+};
+use std::{borrow::Cow, ops::Bound};
+
+pub struct Balances;
+
+impl Mappable for Balances {
+    type Key = u32;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+fn storage() -> MemoryStorage {
+    let mut storage = MemoryStorage::new();
+    for key in 0..10 {
+        storage
+            .storage::<Balances>()
+            .insert(&key, &(key as u64))
+            .unwrap();
+    }
+    storage
+}
+
+fn get(cached: &mut CachedStorage<&mut MemoryStorage>, key: u32) -> Option<u64> {
+    cached
+        .storage::<Balances>()
+        .get(&key)
+        .unwrap()
+        .map(|value| *value)
+}
+
+#[test]
+fn reads_evict_the_least_recently_used_values() {
+    let mut storage = storage();
+    let mut cached = CachedStorage::new(&mut storage, CacheCapacity::Entries(2));
+    assert_eq!(get(&mut cached, 0), Some(0));
+    assert_eq!(get(&mut cached, 1), Some(1));
+    // The hit makes 1 the least recently used value.
+    assert_eq!(get(&mut cached, 0), Some(0));
+    assert_eq!(get(&mut cached, 2), Some(2));
+    assert_eq!(get(&mut cached, 0), Some(0));
+    assert_eq!(get(&mut cached, 1), Some(1));
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 2, misses: 4 }
+    );
+
+    // Absent keys aren't cached.
+    assert_eq!(get(&mut cached, 20), None);
+    assert_eq!(get(&mut cached, 20), None);
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 2, misses: 6 }
+    );
+
+    cached.clear();
+    assert_eq!(get(&mut cached, 1), Some(1));
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 2, misses: 7 }
+    );
+}
+
+#[test]
+fn writes_invalidate_the_cached_values() {
+    let mut storage = storage();
+    let mut cached = CachedStorage::new(&mut storage, CacheCapacity::Entries(4));
+    assert_eq!(get(&mut cached, 0), Some(0));
+    assert_eq!(get(&mut cached, 1), Some(1));
+
+    assert_eq!(cached.storage::<Balances>().insert(&0, &10), Ok(Some(0)));
+    assert_eq!(get(&mut cached, 0), Some(10));
+    assert_eq!(cached.storage::<Balances>().remove(&1), Ok(Some(1)));
+    assert_eq!(get(&mut cached, 1), None);
+    assert!(!cached.storage::<Balances>().contains_key(&1).unwrap());
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 0, misses: 4 }
+    );
+}
+
+#[test]
+fn byte_capacity_bounds_the_weight_of_the_cached_values() {
+    let mut storage = storage();
+    let mut cached = CachedStorage::new(&mut storage, CacheCapacity::Bytes(100));
+    cached.set_weigher::<Balances>(|key, _| 40 + *key as usize);
+    for _ in 0..2 {
+        // Both values fit, 40 and 41 bytes.
+        assert_eq!(get(&mut cached, 0), Some(0));
+        assert_eq!(get(&mut cached, 1), Some(1));
+    }
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 2, misses: 2 }
+    );
+    // The third value evicts the least recently used one.
+    assert_eq!(get(&mut cached, 2), Some(2));
+    assert_eq!(get(&mut cached, 1), Some(1));
+    assert_eq!(get(&mut cached, 0), Some(0));
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 3, misses: 4 }
+    );
+
+    // The value heavier than the whole capacity is never cached.
+    let mut cached = CachedStorage::new(&mut storage, CacheCapacity::Bytes(0));
+    assert_eq!(get(&mut cached, 1), Some(1));
+    assert_eq!(get(&mut cached, 1), Some(1));
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 0, misses: 2 }
+    );
+}
+
+#[test]
+fn iterations_leave_the_cache_alone() {
+    let mut storage = storage();
+    let mut cached = CachedStorage::new(&mut storage, CacheCapacity::Entries(4));
+    assert_eq!(get(&mut cached, 2), Some(2));
+    assert_eq!(get(&mut cached, 8), Some(8));
+
+    let items: Vec<_> = cached
+        .storage::<Balances>()
+        .iter_range(
+            Bound::Included(&1),
+            Bound::Excluded(&4),
+            IterDirection::Reverse,
+        )
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(items, [(3, 3), (2, 2), (1, 1)]);
+
+    // Both the iterated key and the other one stay cached.
+    assert_eq!(get(&mut cached, 2), Some(2));
+    assert_eq!(get(&mut cached, 8), Some(8));
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 2, misses: 2 }
+    );
+
+    let keys: Vec<_> = cached
+        .storage::<Balances>()
+        .iter_all(IterDirection::Forward)
+        .map(|item| item.unwrap().0)
+        .collect();
+    assert_eq!(keys, (0..10).collect::<Vec<_>>());
+    assert_eq!(get(&mut cached, 8), Some(8));
+    assert_eq!(
+        cached.stats::<Balances>(),
+        CacheStats { hits: 3, misses: 2 }
+    );
+}
+
+#[test]
+fn merkle_methods_go_to_the_storage_and_drop_the_cache() {
+    let mut storage = state_storage();
+    let mut leaves = Vec::new();
+    for slot in 0..20u32 {
+        let key = (0, slot);
+        let value = slot as u64 * 3;
+        storage
+            .storage::<ContractsState>()
+            .insert(&key, &value)
+            .unwrap();
+        leaves.push((path(&key), Sha256::hash(&value.to_be_bytes())));
+    }
+    leaves.sort();
+    let mut expected = storage.clone();
+
+    let mut cached = CachedStorage::new(&mut storage, CacheCapacity::Entries(8));
+    assert_eq!(
+        cached.storage::<ContractsState>().get(&(0, 5)).unwrap(),
+        Some(Cow::Owned(15))
+    );
+    assert_eq!(
+        cached.storage::<ContractsState>().root(&0).unwrap(),
+        reference_root(&leaves, 0)
+    );
+    assert_eq!(
+        cached
+            .storage::<ContractsState>()
+            .prove(&0, &(0, 5))
+            .unwrap(),
+        expected
+            .storage::<ContractsState>()
+            .prove(&0, &(0, 5))
+            .unwrap()
+    );
+    assert_eq!(
+        cached
+            .storage::<ContractsState>()
+            .prove(&0, &(0, 50))
+            .unwrap(),
+        expected
+            .storage::<ContractsState>()
+            .prove(&0, &(0, 50))
+            .unwrap()
+    );
+
+    // The merkle methods dropped the cached value, so it is read from the storage again.
+    cached.storage::<ContractsState>().get(&(0, 5)).unwrap();
+    assert_eq!(
+        cached.stats::<ContractsState>(),
+        CacheStats { hits: 0, misses: 2 }
+    );
+}
@@ -0,0 +1,317 @@
+use core::ops::Bound;
+use fuel_storage::{
+    codec::DecodeError,
+    kv_store::{IterableKeyValueStore, KeyValueStore},
+    merkle::Sha256,
+    Changes, IterDirection, KeyValueOverlay, MemoryKeyValueStore,
+};
+
+fn column(store: &impl IterableKeyValueStore, column: u32) -> Vec<(Vec<u8>, Vec<u8>)> {
+    store
+        .iter_column(
+            column,
+            Bound::Unbounded,
+            Bound::Unbounded,
+            IterDirection::Forward,
+        )
+        .map(|item| item.ok().unwrap())
+        .collect()
+}
+
+#[test]
+fn overlays_buffer_the_writes_as_changes() {
+    let mut store = MemoryKeyValueStore::new();
+    for key in [1, 3, 5] {
+        store.put(0, &[key], &[key]).unwrap();
+    }
+
+    let mut overlay = KeyValueOverlay::new(&mut store);
+    overlay.put(0, &[2], &[20]).unwrap();
+    overlay.delete(0, &[3]).unwrap();
+    overlay.put(0, &[5], &[50]).unwrap();
+    overlay.put(1, &[9], &[9]).unwrap();
+    assert_eq!(
+        column(&overlay, 0),
+        vec![(vec![1], vec![1]), (vec![2], vec![20]), (vec![5], vec![50])]
+    );
+    let reverse: Vec<_> = overlay
+        .iter_column(
+            0,
+            Bound::Excluded(&[1][..]),
+            Bound::Unbounded,
+            IterDirection::Reverse,
+        )
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(reverse, vec![(vec![5], vec![50]), (vec![2], vec![20])]);
+    assert!(!overlay.exists(0, &[3]).unwrap());
+    assert_eq!(overlay.inner().get(0, &[3]).unwrap(), Some(vec![3]));
+
+    let (_, changes) = overlay.commit().unwrap();
+    assert_eq!(changes.len(), 4);
+    assert_eq!(changes.get(0, &[3]), Some(None));
+    assert_eq!(changes.get(0, &[1]), None);
+    assert_eq!(
+        column(&store, 0),
+        vec![(vec![1], vec![1]), (vec![2], vec![20]), (vec![5], vec![50])]
+    );
+    assert_eq!(store.get(1, &[9]).unwrap(), Some(vec![9]));
+}
+
+#[test]
+fn rolled_back_overlays_leave_the_store_intact() {
+    let mut store = MemoryKeyValueStore::new();
+    store.put(0, &[1], &[1]).unwrap();
+
+    let mut overlay = KeyValueOverlay::new(&mut store);
+    overlay.delete(0, &[1]).unwrap();
+    overlay.put(0, &[2], &[2]).unwrap();
+    let (_, changes) = overlay.into_parts();
+    assert_eq!(changes.len(), 2);
+
+    assert_eq!(column(&store, 0), vec![(vec![1], vec![1])]);
+}
+
+#[test]
+fn the_binary_format_is_canonical() {
+    let mut changes = Changes::new();
+    changes.put(3, &[1, 2], &[3]);
+    changes.delete(3, &[0]);
+    changes.put(1, &[], &[]);
+    let bytes = changes.encode();
+    assert_eq!(
+        bytes,
+        [1, 2, 1, 1, 0, 1, 0, 3, 2, 1, 0, 0, 2, 1, 2, 1, 1, 3]
+    );
+    assert_eq!(Changes::decode(&bytes), Ok(changes.clone()));
+    assert_eq!(
+        Changes::decode(&bytes).unwrap().hash::<Sha256>(),
+        changes.hash::<Sha256>()
+    );
+
+    let mut unsupported = bytes.clone();
+    unsupported[0] = 2;
+    assert_eq!(
+        Changes::decode(&unsupported),
+        Err(DecodeError::UnsupportedVersion(2))
+    );
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(Changes::decode(&trailing), Err(DecodeError::TrailingBytes));
+
+    // Keys out of order, duplicate columns and empty columns have no canonical encoding.
+    let unordered_keys = [1, 1, 0, 2, 1, 2, 0, 1, 1, 0];
+    assert_eq!(
+        Changes::decode(&unordered_keys),
+        Err(DecodeError::InvalidValue)
+    );
+    let duplicate_columns = [1, 2, 0, 1, 1, 0, 0, 0, 1, 1, 1, 0];
+    assert_eq!(
+        Changes::decode(&duplicate_columns),
+        Err(DecodeError::InvalidValue)
+    );
+    assert_eq!(
+        Changes::decode(&[1, 1, 0, 0]),
+        Err(DecodeError::InvalidValue)
+    );
+}
+
+#[test]
+fn later_changes_override_earlier_ones() {
+    let mut block = Changes::new();
+    block.put(0, &[1], &[1]);
+    block.put(0, &[2], &[2]);
+    let mut next = Changes::new();
+    next.delete(0, &[1]);
+    next.put(1, &[1], &[1]);
+    block.extend(next);
+
+    let all: Vec<_> = block.iter().collect();
+    assert_eq!(
+        all,
+        [
+            (0, &[1][..], None),
+            (0, &[2][..], Some(&[2][..])),
+            (1, &[1][..], Some(&[1][..]))
+        ]
+    );
+}
+
+mod reversible {
+    use super::column;
+    use core::ops::Bound;
+    use fuel_storage::{
+        codec::{Compact, DecodeError, Encode},
+        kv_store::{IterableKeyValueStore, KeyValueItem, KeyValueStore},
+        BoxedIter, Changes, IterDirection, Mappable, MemoryKeyValueStore, StorageAsMut,
+        StorageTransaction, StructuredStorage, Table, TableWithCodec,
+    };
+
+    pub struct Balances;
+
+    impl Mappable for Balances {
+        type Key = u32;
+        type SetValue = u64;
+        type GetValue = u64;
+    }
+
+    impl Table for Balances {
+        const COLUMN: u32 = 7;
+        const NAME: &'static str = "Balances";
+    }
+
+    impl TableWithCodec for Balances {
+        type KeyCodec = Compact;
+        type ValueCodec = Compact;
+    }
+
+    /// The balance that the failing store refuses to write.
+    const POISON: u64 = 666;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Error {
+        Write,
+        Codec(DecodeError),
+    }
+
+    impl From<DecodeError> for Error {
+        fn from(error: DecodeError) -> Self {
+            Self::Codec(error)
+        }
+    }
+
+    /// The store that fails to write the `POISON` balance, after the writes preceding it.
+    #[derive(Default)]
+    pub struct FailingStore(MemoryKeyValueStore);
+
+    impl KeyValueStore for FailingStore {
+        type Error = Error;
+
+        fn get(&self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+            Ok(self.0.get(column, key).unwrap())
+        }
+
+        fn put(&mut self, column: u32, key: &[u8], value: &[u8]) -> Result<(), Error> {
+            if value == &*<Compact as Encode<u64>>::encode(&POISON) {
+                return Err(Error::Write);
+            }
+            self.0.put(column, key, value).unwrap();
+            Ok(())
+        }
+
+        fn delete(&mut self, column: u32, key: &[u8]) -> Result<(), Error> {
+            self.0.delete(column, key).unwrap();
+            Ok(())
+        }
+    }
+
+    impl IterableKeyValueStore for FailingStore {
+        fn iter_column(
+            &self,
+            column: u32,
+            start: Bound<&[u8]>,
+            end: Bound<&[u8]>,
+            direction: IterDirection,
+        ) -> BoxedIter<'_, KeyValueItem<Error>> {
+            let items = self.0.iter_column(column, start, end, direction);
+            Box::new(items.map(|item| Ok(item.unwrap())))
+        }
+    }
+
+    fn storage() -> StructuredStorage<FailingStore> {
+        let mut storage = StructuredStorage::new(FailingStore::default());
+        for key in [2, 4] {
+            storage
+                .storage::<Balances>()
+                .insert(&key, &(key as u64))
+                .unwrap();
+        }
+        storage
+    }
+
+    fn block() -> Changes {
+        let mut changes = Changes::new();
+        changes.insert::<Balances>(&1, &10);
+        changes.remove::<Balances>(&2);
+        changes.insert::<Balances>(&4, &40);
+        changes
+    }
+
+    #[test]
+    fn inverse_changes_restore_the_previous_state() {
+        let mut storage = storage();
+        let before = column(storage.inner(), Balances::COLUMN);
+
+        let inverse = block().apply_reversibly(storage.inner_mut()).unwrap();
+        let mut expected = Changes::new();
+        expected.remove::<Balances>(&1);
+        expected.insert::<Balances>(&2, &2);
+        expected.insert::<Balances>(&4, &4);
+        assert_eq!(inverse, expected);
+        assert_eq!(
+            storage
+                .storage::<Balances>()
+                .get(&4)
+                .unwrap()
+                .unwrap()
+                .into_owned(),
+            40
+        );
+
+        assert_eq!(inverse.apply_reversibly(storage.inner_mut()), Ok(block()));
+        assert_eq!(column(storage.inner(), Balances::COLUMN), before);
+    }
+
+    #[test]
+    fn tables_are_applied_reversibly_through_transactions() {
+        let mut storage = storage();
+        let before = column(storage.inner(), Balances::COLUMN);
+
+        let mut transaction = StorageTransaction::new(&mut storage);
+        let inverse = block()
+            .apply_table::<Balances, _>(&mut transaction)
+            .unwrap();
+        transaction.commit::<Error>().unwrap();
+        assert!(!storage.storage::<Balances>().contains_key(&2).unwrap());
+
+        assert_eq!(
+            inverse.apply_table::<Balances, _>(&mut storage),
+            Ok(block())
+        );
+        assert_eq!(column(storage.inner(), Balances::COLUMN), before);
+    }
+
+    #[test]
+    fn failed_applications_are_undone() {
+        let mut storage = storage();
+        let before = column(storage.inner(), Balances::COLUMN);
+        let mut changes = block();
+        changes.insert::<Balances>(&5, &POISON);
+
+        assert_eq!(
+            changes.apply_reversibly(storage.inner_mut()),
+            Err(Error::Write)
+        );
+        assert_eq!(column(storage.inner(), Balances::COLUMN), before);
+
+        assert_eq!(
+            changes.apply_table::<Balances, _>(&mut storage),
+            Err(Error::Write)
+        );
+        assert_eq!(column(storage.inner(), Balances::COLUMN), before);
+    }
+
+    #[test]
+    fn invalid_encodings_are_rejected_before_writing() {
+        let mut storage = storage();
+        let before = column(storage.inner(), Balances::COLUMN);
+        let mut changes = block();
+        changes.put(Balances::COLUMN, &[0xff], &[1]);
+
+        assert!(matches!(
+            changes.apply_table::<Balances, _>(&mut storage),
+            Err(Error::Codec(_))
+        ));
+        assert_eq!(column(storage.inner(), Balances::COLUMN), before);
+    }
+}
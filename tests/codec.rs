@@ -0,0 +1,76 @@
+use fuel_storage::codec::{
+    Array, BigEndian, Compact, CompactDecode, CompactEncode, Decode, DecodeError, Encode, Raw,
+};
+
+fn round_trip<T>(value: T) -> Vec<u8>
+where
+    T: CompactEncode + CompactDecode + PartialEq + core::fmt::Debug,
+{
+    let encoded = Compact::encode(&value).into_owned();
+    assert_eq!(<Compact as Decode<T>>::decode(&encoded), Ok(value));
+    encoded
+}
+
+#[test]
+fn compact_integers_use_varints() {
+    assert_eq!(round_trip(0u32), vec![0]);
+    assert_eq!(round_trip(127u32), vec![127]);
+    assert_eq!(round_trip(128u32), vec![0x80, 1]);
+    assert_eq!(round_trip(u16::MAX), vec![0xff, 0xff, 3]);
+    assert_eq!(round_trip(-1i32), vec![1]);
+    assert_eq!(round_trip(1i32), vec![2]);
+    round_trip(u64::MAX);
+    round_trip(u128::MAX);
+    round_trip(i64::MIN);
+    round_trip(i64::MAX);
+}
+
+#[test]
+fn compact_composites_round_trip() {
+    round_trip((1u8, vec![1u16, 500], Some(String::from("fuel")), [7u8; 3]));
+    round_trip(Option::<u64>::None);
+    round_trip(Vec::<Vec<u8>>::new());
+}
+
+#[test]
+fn compact_rejects_malformed_bytes() {
+    assert_eq!(
+        <Compact as Decode<u16>>::decode(&[0xff, 0xff, 4]),
+        Err(DecodeError::InvalidValue)
+    );
+    assert_eq!(
+        <Compact as Decode<u16>>::decode(&[0x80, 0]),
+        Err(DecodeError::InvalidValue)
+    );
+    assert_eq!(
+        <Compact as Decode<u16>>::decode(&[0x80]),
+        Err(DecodeError::UnexpectedEnd)
+    );
+    assert_eq!(
+        <Compact as Decode<u8>>::decode(&[1, 2]),
+        Err(DecodeError::TrailingBytes)
+    );
+}
+
+#[test]
+fn big_endian_preserves_the_order_of_integers() {
+    let encoded: Vec<_> = [0u32, 1, 255, 256, u32::MAX]
+        .iter()
+        .map(|value| BigEndian::encode(value).into_owned())
+        .collect();
+    assert!(encoded.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(<BigEndian as Decode<u32>>::decode(&encoded[3]), Ok(256));
+    assert!(<BigEndian as Decode<u32>>::decode(&[1, 2, 3]).is_err());
+}
+
+#[test]
+fn arrays_and_raw_bytes_are_stored_as_is() {
+    assert_eq!(Array::encode(&[1u8, 2, 3]).as_ref(), &[1, 2, 3]);
+    assert_eq!(
+        <Array as Decode<[u8; 3]>>::decode(&[1, 2, 3]),
+        Ok([1, 2, 3])
+    );
+    assert!(<Array as Decode<[u8; 3]>>::decode(&[1, 2]).is_err());
+    assert_eq!(Raw::encode(&[4u8, 5][..]).as_ref(), &[4, 5]);
+    assert_eq!(<Raw as Decode<Vec<u8>>>::decode(&[4, 5]), Ok(vec![4, 5]));
+}
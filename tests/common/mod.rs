@@ -0,0 +1,119 @@
+//! The tables and helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use fuel_storage::{
+    codec::{Array, BigEndian, Compact, Encode},
+    merkle::{MerkleHasher, Sha256, SparseMerkleStorage, SparseMerkleTable, SparseNode, ValueHash},
+    Mappable, MemoryKeyValueStore, MerkleRoot, StructuredStorage, Table, TableWithCodec,
+};
+
+/// The contract state: the slot of the contract with the value, one sparse tree per contract.
+pub struct ContractsState;
+
+impl Mappable for ContractsState {
+    type Key = (u8, u32);
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+impl Table for ContractsState {
+    const COLUMN: u32 = 0;
+    const NAME: &'static str = "ContractsState";
+}
+
+impl TableWithCodec for ContractsState {
+    type KeyCodec = Compact;
+    type ValueCodec = BigEndian;
+}
+
+impl SparseMerkleTable for ContractsState {
+    type Kind = ValueHash;
+    type Nodes = StateNodes;
+    type Roots = StateRoots;
+
+    fn tree_key(key: &Self::Key) -> u8 {
+        key.0
+    }
+}
+
+pub struct StateNodes;
+
+impl Mappable for StateNodes {
+    type Key = MerkleRoot;
+    type SetValue = SparseNode;
+    type GetValue = SparseNode;
+}
+
+impl Table for StateNodes {
+    const COLUMN: u32 = 1;
+    const NAME: &'static str = "StateNodes";
+}
+
+impl TableWithCodec for StateNodes {
+    type KeyCodec = Array;
+    type ValueCodec = Compact;
+}
+
+pub struct StateRoots;
+
+impl Mappable for StateRoots {
+    type Key = u8;
+    type SetValue = MerkleRoot;
+    type GetValue = MerkleRoot;
+}
+
+impl Table for StateRoots {
+    const COLUMN: u32 = 2;
+    const NAME: &'static str = "StateRoots";
+}
+
+impl TableWithCodec for StateRoots {
+    type KeyCodec = BigEndian;
+    type ValueCodec = Array;
+}
+
+pub type StateStorage = SparseMerkleStorage<StructuredStorage<MemoryKeyValueStore>>;
+
+pub fn state_storage() -> StateStorage {
+    SparseMerkleStorage::new(StructuredStorage::new(MemoryKeyValueStore::new()))
+}
+
+/// The path of the leaf of the `key` in the sparse tree.
+pub fn path(key: &(u8, u32)) -> MerkleRoot {
+    Sha256::hash(&<Compact as Encode<(u8, u32)>>::encode(key))
+}
+
+/// The root of the sparse tree with the `leaves`, sorted by their paths, computed from scratch.
+pub fn reference_root(leaves: &[(MerkleRoot, MerkleRoot)], depth: usize) -> MerkleRoot {
+    match leaves {
+        [] => MerkleRoot::default(),
+        [(path, data)] => SparseNode::Leaf {
+            path: *path,
+            data: *data,
+        }
+        .digest::<Sha256>()
+        .unwrap(),
+        _ => {
+            let split =
+                leaves.partition_point(|(path, _)| path[depth / 8] >> (7 - depth % 8) & 1 == 0);
+            SparseNode::Branch {
+                left: reference_root(&leaves[..split], depth + 1),
+                right: reference_root(&leaves[split..], depth + 1),
+            }
+            .digest::<Sha256>()
+            .unwrap()
+        }
+    }
+}
+
+/// The xorshift generator of the pseudo-random test data.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,191 @@
+mod common;
+
+use common::{path, reference_root, state_storage, ContractsState, Rng, StateStorage};
+use fuel_storage::{
+    merkle::{MerkleHasher, Sha256},
+    BoxedIter, FilteredStorage, IterDirection, KVItem, Mappable, MemoryStorage, StorageAsMut,
+    StorageInspect, StorageIterate, StorageMutate,
+};
+use std::{borrow::Cow, cell::Cell, convert::Infallible, ops::Bound};
+
+pub struct Coins;
+
+impl Mappable for Coins {
+    type Key = u64;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+/// The storage counting the lookups that reach it.
+#[derive(Default)]
+struct CountingStorage {
+    storage: MemoryStorage,
+    lookups: Cell<usize>,
+}
+
+impl StorageInspect<Coins> for CountingStorage {
+    type Error = Infallible;
+
+    fn get(&self, key: &u64) -> Result<Option<Cow<'_, u64>>, Self::Error> {
+        self.lookups.set(self.lookups.get() + 1);
+        StorageInspect::<Coins>::get(&self.storage, key)
+    }
+
+    fn contains_key(&self, key: &u64) -> Result<bool, Self::Error> {
+        self.lookups.set(self.lookups.get() + 1);
+        StorageInspect::<Coins>::contains_key(&self.storage, key)
+    }
+}
+
+impl StorageMutate<Coins> for CountingStorage {
+    fn insert(&mut self, key: &u64, value: &u64) -> Result<Option<u64>, Self::Error> {
+        self.storage.storage::<Coins>().insert(key, value)
+    }
+
+    fn remove(&mut self, key: &u64) -> Result<Option<u64>, Self::Error> {
+        self.lookups.set(self.lookups.get() + 1);
+        self.storage.storage::<Coins>().remove(key)
+    }
+}
+
+impl StorageIterate<Coins> for CountingStorage {
+    fn iter_range(
+        &self,
+        start: Bound<&u64>,
+        end: Bound<&u64>,
+        direction: IterDirection,
+    ) -> BoxedIter<'_, KVItem<Coins, Self::Error>> {
+        StorageIterate::<Coins>::iter_range(&self.storage, start, end, direction)
+    }
+}
+
+/// The storage with the even coins below 2000.
+fn storage() -> CountingStorage {
+    let mut storage = CountingStorage::default();
+    for key in (0..2000).step_by(2) {
+        storage.storage::<Coins>().insert(&key, &key).unwrap();
+    }
+    storage
+}
+
+#[test]
+fn built_filters_answer_lookups_of_absent_keys() {
+    let mut storage = storage();
+    let mut filtered = FilteredStorage::new(&mut storage, 10);
+    // Without the filter every lookup goes to the storage.
+    assert!(!filtered.is_built::<Coins>());
+    assert!(!filtered.storage::<Coins>().contains_key(&1).unwrap());
+    assert_eq!(filtered.inner().lookups.get(), 1);
+
+    filtered.build::<Coins>().unwrap();
+    assert!(filtered.is_built::<Coins>());
+    for key in (0..2000).step_by(2) {
+        assert!(filtered.storage::<Coins>().contains_key(&key).unwrap());
+        assert_eq!(
+            filtered.storage::<Coins>().get(&key).unwrap(),
+            Some(Cow::Owned(key))
+        );
+    }
+    assert_eq!(filtered.inner().lookups.get(), 2001);
+
+    let mut rng = Rng(777);
+    for _ in 0..10_000 {
+        let key = rng.next() | 1;
+        assert!(!filtered.storage::<Coins>().contains_key(&key).unwrap());
+        assert_eq!(filtered.storage::<Coins>().get(&key).unwrap(), None);
+    }
+    // About 1% of false positives for 10 bits per key.
+    let passed = filtered.inner().lookups.get() - 2001;
+    assert!(
+        passed < 400,
+        "{passed} of 20000 absent lookups reached the storage"
+    );
+}
+
+#[test]
+fn inserts_update_the_filters() {
+    let mut storage = storage();
+    let mut filtered = FilteredStorage::new(&mut storage, 10);
+    filtered.build::<Coins>().unwrap();
+
+    filtered.storage::<Coins>().insert(&1, &1).unwrap();
+    assert!(filtered.storage::<Coins>().contains_key(&1).unwrap());
+    assert_eq!(filtered.storage::<Coins>().remove(&1), Ok(Some(1)));
+    // Removed keys stay in the filter but the storage answers their lookups.
+    assert!(!filtered.storage::<Coins>().contains_key(&1).unwrap());
+    assert_eq!(filtered.storage::<Coins>().remove(&3), Ok(None));
+
+    // Inserts grow the filters of empty tables too.
+    let mut empty = CountingStorage::default();
+    let mut filtered = FilteredStorage::new(&mut empty, 10);
+    filtered.build::<Coins>().unwrap();
+    for key in 0..3000 {
+        filtered.storage::<Coins>().insert(&key, &key).unwrap();
+    }
+    for key in 0..3000 {
+        assert!(filtered.storage::<Coins>().contains_key(&key).unwrap());
+    }
+}
+
+#[test]
+fn iterations_go_to_the_storage() {
+    let mut storage = storage();
+    let mut filtered = FilteredStorage::new(&mut storage, 10);
+    filtered.build::<Coins>().unwrap();
+    filtered.storage::<Coins>().insert(&7, &70).unwrap();
+
+    let items: Vec<_> = filtered
+        .storage::<Coins>()
+        .iter_range(
+            Bound::Included(&3),
+            Bound::Excluded(&10),
+            IterDirection::Forward,
+        )
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(items, [(4, 4), (6, 6), (7, 70), (8, 8)]);
+    assert_eq!(
+        filtered
+            .storage::<Coins>()
+            .iter_all(IterDirection::Reverse)
+            .next()
+            .unwrap(),
+        Ok((1998, 1998))
+    );
+}
+
+#[test]
+fn merkle_methods_go_to_the_storage() {
+    let mut storage = state_storage();
+    let mut filtered = FilteredStorage::new(&mut storage, 10);
+    filtered.build::<ContractsState>().unwrap();
+    let mut leaves = Vec::new();
+    for slot in 0..20u32 {
+        let key = (1, slot);
+        let value = slot as u64 + 100;
+        filtered
+            .storage::<ContractsState>()
+            .insert(&key, &value)
+            .unwrap();
+        leaves.push((path(&key), Sha256::hash(&value.to_be_bytes())));
+    }
+    leaves.sort();
+    let mut expected = StateStorage::clone(filtered.inner());
+
+    assert_eq!(
+        filtered.storage::<ContractsState>().root(&1).unwrap(),
+        reference_root(&leaves, 0)
+    );
+    for storage_key in [(1, 5), (1, 50)] {
+        assert_eq!(
+            filtered
+                .storage::<ContractsState>()
+                .prove(&1, &storage_key)
+                .unwrap(),
+            expected
+                .storage::<ContractsState>()
+                .prove(&1, &storage_key)
+                .unwrap()
+        );
+    }
+}
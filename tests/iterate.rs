@@ -0,0 +1,122 @@
+use core::ops::Bound;
+use fuel_storage::{
+    IterDirection, Mappable, MemoryStorage, StorageAsMut, StorageIterate, StorageTransaction,
+};
+
+pub struct Balances;
+
+impl Mappable for Balances {
+    type Key = u32;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+pub struct Slots;
+
+impl Mappable for Slots {
+    type Key = [u8; 2];
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+fn storage() -> MemoryStorage {
+    let mut storage = MemoryStorage::new();
+    for key in 0..10 {
+        storage
+            .storage::<Balances>()
+            .insert(&key, &(key as u64))
+            .unwrap();
+    }
+    storage
+}
+
+#[test]
+fn iterates_over_ranges_in_both_directions() {
+    let mut storage = storage();
+    let forward: Vec<_> = storage
+        .storage::<Balances>()
+        .iter_range(
+            Bound::Excluded(&2),
+            Bound::Included(&5),
+            IterDirection::Forward,
+        )
+        .map(|item| item.unwrap().0)
+        .collect();
+    assert_eq!(forward, vec![3, 4, 5]);
+
+    let reverse: Vec<_> = storage
+        .storage::<Balances>()
+        .iter_range(
+            Bound::Unbounded,
+            Bound::Excluded(&3),
+            IterDirection::Reverse,
+        )
+        .map(|item| item.unwrap().0)
+        .collect();
+    assert_eq!(reverse, vec![2, 1, 0]);
+
+    let empty = storage.storage::<Balances>().iter_range(
+        Bound::Excluded(&2),
+        Bound::Excluded(&2),
+        IterDirection::Forward,
+    );
+    assert_eq!(empty.count(), 0);
+}
+
+#[test]
+fn iterates_over_prefixes() {
+    let mut storage = MemoryStorage::new();
+    for key in [[1, 2], [1, 3], [2, 2], [0, 9]] {
+        storage.storage::<Slots>().insert(&key, &1).unwrap();
+    }
+
+    let keys: Vec<_> = storage
+        .storage::<Slots>()
+        .iter_prefix(&[1], IterDirection::Reverse)
+        .map(|item| item.unwrap().0)
+        .collect();
+    assert_eq!(keys, vec![[1, 3], [1, 2]]);
+}
+
+#[test]
+fn transactions_merge_the_overlay_into_the_iteration() {
+    let mut storage = storage();
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction.storage::<Balances>().remove(&3).unwrap();
+    transaction.storage::<Balances>().insert(&4, &40).unwrap();
+    transaction.storage::<Balances>().insert(&20, &20).unwrap();
+    let savepoint = transaction.savepoint();
+    transaction.storage::<Balances>().insert(&3, &30).unwrap();
+    transaction.storage::<Balances>().remove(&0).unwrap();
+
+    let all: Vec<_> = StorageIterate::<Balances>::iter_all(&transaction, IterDirection::Forward)
+        .map(|item| item.unwrap())
+        .collect();
+    assert_eq!(
+        all,
+        vec![
+            (1, 1),
+            (2, 2),
+            (3, 30),
+            (4, 40),
+            (5, 5),
+            (6, 6),
+            (7, 7),
+            (8, 8),
+            (9, 9),
+            (20, 20)
+        ]
+    );
+
+    transaction.rollback_to(savepoint);
+    let reverse: Vec<_> = transaction
+        .storage::<Balances>()
+        .iter_range(
+            Bound::Included(&2),
+            Bound::Excluded(&20),
+            IterDirection::Reverse,
+        )
+        .map(|item| item.unwrap().0)
+        .collect();
+    assert_eq!(reverse, vec![9, 8, 7, 6, 5, 4, 2]);
+}
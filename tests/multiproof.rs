@@ -0,0 +1,74 @@
+mod common;
+
+use common::{state_storage, ContractsState, Rng};
+use fuel_storage::{
+    codec::{CompactDecode, CompactEncode},
+    merkle::{verify_multiproof, Sha256, SparseMerkleMultiproof},
+    StorageAsMut,
+};
+use std::collections::BTreeMap;
+
+#[test]
+fn random_multiproofs_verify_and_roundtrip() {
+    let mut storage = state_storage();
+    let mut model = BTreeMap::new();
+    let mut rng = Rng(99);
+    for _ in 0..300 {
+        let key = (0, (rng.next() % 200) as u32);
+        let value = rng.next();
+        storage
+            .storage::<ContractsState>()
+            .insert(&key, &value)
+            .unwrap();
+        model.insert(key, value);
+    }
+    let root = storage.storage::<ContractsState>().root(&0).unwrap();
+
+    for _ in 0..200 {
+        let count = (rng.next() % 20) as usize;
+        let keys: Vec<_> = (0..count).map(|_| (0, (rng.next() % 260) as u32)).collect();
+        let proof = storage
+            .storage::<ContractsState>()
+            .prove_many(&0, &keys)
+            .unwrap();
+        let entries: Vec<_> = keys.iter().map(|key| (key, model.get(key))).collect();
+        assert!(verify_multiproof::<ContractsState, Sha256>(
+            &root, &entries, &proof
+        ));
+
+        let mut bytes = Vec::new();
+        proof.encode_compact(&mut bytes);
+        assert_eq!(
+            SparseMerkleMultiproof::decode_compact(&mut &bytes[..]),
+            Ok(proof.clone())
+        );
+
+        if count > 0 {
+            assert!(!verify_multiproof::<ContractsState, Sha256>(
+                &[1; 32], &entries, &proof
+            ));
+
+            // Flipping the presence of any entry invalidates the proof.
+            let wrong = 12345;
+            let mut flipped = entries.clone();
+            let index = rng.next() as usize % count;
+            flipped[index].1 = match flipped[index].1 {
+                Some(_) => None,
+                None => Some(&wrong),
+            };
+            assert!(!verify_multiproof::<ContractsState, Sha256>(
+                &root, &flipped, &proof
+            ));
+
+            // The shared siblings are carried once.
+            let separate: usize = keys
+                .iter()
+                .map(|key| {
+                    let proof = storage.storage::<ContractsState>().prove(&0, key).unwrap();
+                    proof.siblings.len()
+                })
+                .sum();
+            assert!(proof.siblings.len() <= separate);
+        }
+    }
+}
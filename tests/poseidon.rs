@@ -0,0 +1,55 @@
+mod common;
+
+use common::ContractsState;
+use fuel_storage::{
+    merkle::{verify, MerkleHasher, Poseidon, Sha256, SparseMerkleStorage},
+    MemoryKeyValueStore, StorageAsMut, StructuredStorage,
+};
+
+#[test]
+fn incremental_updates_match_the_one_shot_hash() {
+    let data: Vec<u8> = (0..200).map(|byte| byte as u8).collect();
+    for split in [0, 1, 55, 56, 57, 112, 199, 200] {
+        let mut hasher = Poseidon::default();
+        hasher.update(&data[..split]);
+        hasher.update(&data[split..]);
+        assert_eq!(hasher.finalize(), Poseidon::hash(&data));
+    }
+}
+
+#[test]
+fn padding_distinguishes_lengths() {
+    assert_ne!(Poseidon::hash(&[]), Poseidon::hash(&[0]));
+    assert_ne!(Poseidon::hash(&[0; 55]), Poseidon::hash(&[0; 56]));
+    assert_ne!(Poseidon::hash(&[0; 56]), Poseidon::hash(&[0; 57]));
+}
+
+#[test]
+fn proofs_verify_only_with_the_hasher_of_the_tree() {
+    let mut storage: SparseMerkleStorage<_, Poseidon> =
+        SparseMerkleStorage::new(StructuredStorage::new(MemoryKeyValueStore::new()));
+    for slot in 0..50 {
+        storage
+            .storage::<ContractsState>()
+            .insert(&(0, slot), &(slot as u64))
+            .unwrap();
+    }
+
+    let root = storage.storage::<ContractsState>().root(&0).unwrap();
+    let proof = storage
+        .storage::<ContractsState>()
+        .prove(&0, &(0, 7))
+        .unwrap();
+    assert!(verify::<ContractsState, Poseidon>(
+        &root,
+        &(0, 7),
+        Some(&7),
+        &proof
+    ));
+    assert!(!verify::<ContractsState, Sha256>(
+        &root,
+        &(0, 7),
+        Some(&7),
+        &proof
+    ));
+}
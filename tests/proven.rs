@@ -0,0 +1,108 @@
+mod common;
+
+use common::{state_storage, ContractsState, Rng};
+use fuel_storage::{
+    merkle::{MerkleError, ProvenStorage},
+    StorageAsMut, StorageError,
+};
+use std::collections::BTreeMap;
+
+#[test]
+fn replays_updates_of_the_full_storage() {
+    let mut storage = state_storage();
+    let mut model = BTreeMap::new();
+    let mut rng = Rng(5);
+    for _ in 0..60 {
+        let key = (0, (rng.next() % 100) as u32);
+        let value = rng.next();
+        storage
+            .storage::<ContractsState>()
+            .insert(&key, &value)
+            .unwrap();
+        model.insert(key, value);
+    }
+
+    let keys: Vec<_> = (0..100).map(|slot| (0, slot)).collect();
+    for block in 0..30 {
+        let root = storage.storage::<ContractsState>().root(&0).unwrap();
+        let mut proven = ProvenStorage::<ContractsState>::new(root);
+        if block % 2 == 0 {
+            let proof = storage
+                .storage::<ContractsState>()
+                .prove_many(&0, &keys)
+                .unwrap();
+            let entries: Vec<_> = keys.iter().map(|key| (key, model.get(key))).collect();
+            proven.add_multiproof(&entries, &proof).unwrap();
+        } else {
+            for key in &keys {
+                let proof = storage.storage::<ContractsState>().prove(&0, key).unwrap();
+                proven.add_proof(key, model.get(key), &proof).unwrap();
+            }
+        }
+
+        for _ in 0..20 {
+            let key = (0, (rng.next() % 100) as u32);
+            if rng.next().is_multiple_of(3) {
+                assert_eq!(
+                    proven.storage::<ContractsState>().remove(&key),
+                    storage.storage::<ContractsState>().remove(&key)
+                );
+                model.remove(&key);
+            } else {
+                let value = rng.next();
+                assert_eq!(
+                    proven.storage::<ContractsState>().insert(&key, &value),
+                    storage.storage::<ContractsState>().insert(&key, &value)
+                );
+                model.insert(key, value);
+            }
+            assert_eq!(
+                proven.root(),
+                storage.storage::<ContractsState>().root(&0).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn rejects_unproven_keys_and_wrong_values() {
+    let mut storage = state_storage();
+    for slot in 0..10 {
+        storage
+            .storage::<ContractsState>()
+            .insert(&(0, slot), &(slot as u64))
+            .unwrap();
+    }
+    let root = storage.storage::<ContractsState>().root(&0).unwrap();
+    let mut proven = ProvenStorage::<ContractsState>::new(root);
+    assert!(matches!(
+        proven.storage::<ContractsState>().get(&(0, 1)),
+        Err(StorageError::Merkle(MerkleError::Unproven(_)))
+    ));
+
+    let proof = storage
+        .storage::<ContractsState>()
+        .prove(&0, &(0, 1))
+        .unwrap();
+    assert_eq!(
+        proven.add_proof(&(0, 1), Some(&2), &proof),
+        Err(StorageError::Merkle(MerkleError::InvalidProof))
+    );
+    assert_eq!(
+        proven.add_proof(&(0, 1), None, &proof),
+        Err(StorageError::Merkle(MerkleError::InvalidProof))
+    );
+    proven.add_proof(&(0, 1), Some(&1), &proof).unwrap();
+    assert_eq!(
+        proven
+            .storage::<ContractsState>()
+            .get(&(0, 1))
+            .unwrap()
+            .map(|value| value.into_owned()),
+        Some(1)
+    );
+    assert!(matches!(
+        proven.storage::<ContractsState>().insert(&(0, 2), &2),
+        Err(StorageError::Merkle(MerkleError::Unproven(_)))
+    ));
+}
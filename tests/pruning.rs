@@ -0,0 +1,181 @@
+mod common;
+
+use common::{ContractsState, Rng, StateNodes, StateRoots};
+use core::ops::Bound;
+use fuel_storage::{
+    codec::{Compact, Decode},
+    kv_store::IterableKeyValueStore,
+    merkle::{SparseMerkleStorage, SparseNode},
+    Changes, IterDirection, KeyValueOverlay, MemoryKeyValueStore, MerkleRoot, PruningPolicy,
+    StorageAsMut, StorageInspect, StructuredStorage, Table, VersionedStorage,
+};
+use std::collections::BTreeMap;
+
+type Versioned = VersionedStorage<MemoryKeyValueStore>;
+
+fn column_len(store: &MemoryKeyValueStore, column: u32) -> usize {
+    store.column(column).map_or(0, |column| column.len())
+}
+
+fn check_policy(policy: PruningPolicy, seed: u64) {
+    let mut rng = Rng(seed);
+    let mut storage = VersionedStorage::with_policy(MemoryKeyValueStore::new(), policy);
+    let mut states = BTreeMap::new();
+    let mut state = BTreeMap::new();
+    let mut height = 3;
+    let mut pruned_to = 0;
+    for _ in 0..60 {
+        height += 1 + rng.next() % 2;
+        let mut block = Changes::new();
+        for _ in 0..rng.next() % 4 {
+            let key = vec![(rng.next() % 6) as u8];
+            if rng.next().is_multiple_of(3) {
+                block.delete(0, &key);
+                state.remove(&key);
+            } else {
+                let value = rng.next().to_be_bytes().to_vec();
+                block.put(0, &key, &value);
+                state.insert(key, value);
+            }
+        }
+        storage.commit(height, &block).unwrap();
+        states.insert(height, state.clone());
+        if rng.next().is_multiple_of(10) {
+            let prune_height = height - rng.next() % 8;
+            storage.prune(prune_height).unwrap();
+            pruned_to = pruned_to.max(prune_height);
+        }
+
+        for (height, state) in &states {
+            if storage.is_retained(*height).unwrap() {
+                let view = storage.storage_at(*height).unwrap();
+                for key in 0..6 {
+                    assert_eq!(view.get(0, &[key]).unwrap().as_ref(), state.get(&vec![key]));
+                }
+            } else {
+                assert!(storage.storage_at(*height).is_err());
+            }
+        }
+    }
+
+    let (lowest, latest) = storage.heights().unwrap().unwrap();
+    assert_eq!(latest, height);
+    assert!(storage.is_retained(lowest).unwrap());
+    let retained = states
+        .keys()
+        .filter(|height| storage.is_retained(**height).unwrap())
+        .count();
+    match policy {
+        PruningPolicy::Archive => {
+            assert!(states
+                .keys()
+                .all(|height| storage.is_retained(*height).unwrap() == (*height >= pruned_to)));
+        }
+        PruningPolicy::KeepLast(count) => {
+            assert!(retained as u64 <= count);
+            assert!((lowest..=latest).all(|height| storage.is_retained(height).unwrap()));
+        }
+        PruningPolicy::Checkpoints(interval) => {
+            assert!(states.keys().all(|height| {
+                let checkpoint = height.is_multiple_of(interval) || *height == latest;
+                storage.is_retained(*height).unwrap() == checkpoint
+            }));
+        }
+    }
+    // Nothing obsolete survives once no older height reads it.
+    if retained == 1 {
+        assert_eq!(column_len(storage.inner(), Versioned::HISTORY_COLUMN), 0);
+    }
+}
+
+#[test]
+fn policies_keep_the_retained_states() {
+    for seed in 1..20 {
+        check_policy(PruningPolicy::Archive, seed);
+        check_policy(PruningPolicy::KeepLast(1), seed);
+        check_policy(PruningPolicy::KeepLast(5), seed);
+        check_policy(PruningPolicy::Checkpoints(7), seed);
+    }
+}
+
+/// Return the number of the values of the `column` replaced by the commits kept in the history.
+fn replaced_values(store: &MemoryKeyValueStore, column: u32) -> usize {
+    store
+        .iter_column(
+            Versioned::HISTORY_COLUMN,
+            Bound::Unbounded,
+            Bound::Unbounded,
+            IterDirection::Forward,
+        )
+        .map(Result::unwrap)
+        .filter(|(key, replaced)| {
+            key[..4] == column.to_be_bytes()
+                && <Compact as Decode<Option<Vec<u8>>>>::decode(replaced)
+                    .unwrap()
+                    .is_some()
+        })
+        .count()
+}
+
+/// Return the number of the nodes of the tree with the `root`, panicking on a missing node.
+fn tree_nodes(storage: &StructuredStorage<MemoryKeyValueStore>, root: &MerkleRoot) -> usize {
+    if *root == MerkleRoot::default() {
+        return 0;
+    }
+    let node = StorageInspect::<StateNodes>::get(storage, root)
+        .unwrap()
+        .unwrap();
+    match node.into_owned() {
+        SparseNode::Leaf { .. } => 1,
+        SparseNode::Branch { left, right } => {
+            1 + tree_nodes(storage, &left) + tree_nodes(storage, &right)
+        }
+    }
+}
+
+#[test]
+fn pruning_reclaims_the_replaced_values_of_merkle_tables() {
+    let mut storage = VersionedStorage::new(MemoryKeyValueStore::new());
+    let mut rng = Rng(3);
+    let mut roots = Vec::new();
+    for height in 1..=10 {
+        let overlay = KeyValueOverlay::new(storage.inner().clone());
+        let mut block: SparseMerkleStorage<_> =
+            SparseMerkleStorage::new(StructuredStorage::new(overlay));
+        for _ in 0..20 {
+            let key = (0, (rng.next() % 50) as u32);
+            if rng.next().is_multiple_of(4) {
+                block.storage::<ContractsState>().remove(&key).unwrap();
+            } else {
+                let value = rng.next();
+                block
+                    .storage::<ContractsState>()
+                    .insert(&key, &value)
+                    .unwrap();
+            }
+        }
+        roots.push(block.storage::<ContractsState>().root(&0).unwrap());
+        let (_, changes) = block.into_inner().into_inner().into_parts();
+        storage.commit(height, &changes).unwrap();
+    }
+
+    for (height, root) in (1..).zip(&roots) {
+        let view = storage.storage_at(height).unwrap();
+        assert!(StorageInspect::<StateNodes>::contains_key(&view, root).unwrap());
+        let stored = StorageInspect::<StateRoots>::get(&view, &0).unwrap();
+        assert_eq!(stored.as_deref(), Some(root));
+    }
+
+    assert!(replaced_values(storage.inner(), ContractsState::COLUMN) > 0);
+    assert!(replaced_values(storage.inner(), StateRoots::COLUMN) > 0);
+    storage.prune(10).unwrap();
+    assert_eq!(replaced_values(storage.inner(), ContractsState::COLUMN), 0);
+    assert_eq!(replaced_values(storage.inner(), StateRoots::COLUMN), 0);
+    assert!(storage.storage_at(9).is_err());
+
+    // The nodes are never replaced, so the trees stay whole after the pruning.
+    let current = StructuredStorage::new(storage.inner().clone());
+    for root in &roots {
+        assert!(tree_nodes(&current, root) > 0);
+    }
+}
@@ -0,0 +1,101 @@
+use fuel_storage::{Mappable, MemoryStorage, RecordingStorage, StorageAsMut};
+
+pub struct Balances;
+
+impl Mappable for Balances {
+    type Key = u32;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+pub struct Nonces;
+
+impl Mappable for Nonces {
+    type Key = u32;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+fn storage() -> MemoryStorage {
+    let mut storage = MemoryStorage::new();
+    for key in 0..5 {
+        storage
+            .storage::<Balances>()
+            .insert(&key, &(key as u64 * 10))
+            .unwrap();
+    }
+    storage
+}
+
+#[test]
+fn writes_record_the_previous_values_apart_from_reads() {
+    let mut storage = storage();
+    let mut recording = RecordingStorage::new(&mut storage);
+    recording.storage::<Balances>().insert(&1, &11).unwrap();
+    recording.storage::<Balances>().insert(&7, &70).unwrap();
+    recording.storage::<Balances>().remove(&2).unwrap();
+    recording.storage::<Balances>().remove(&8).unwrap();
+
+    // The blind writes don't read the keys.
+    assert!(recording.reads::<Balances>().is_empty());
+    let previous: Vec<_> = recording.previous::<Balances>().into_iter().collect();
+    assert_eq!(
+        previous,
+        [(1, Some(10)), (2, Some(20)), (7, None), (8, None)]
+    );
+    let writes: Vec<_> = recording.writes::<Balances>().into_iter().collect();
+    assert_eq!(writes, [(1, Some(11)), (2, None), (7, Some(70)), (8, None)]);
+}
+
+#[test]
+fn reads_keep_the_values_before_the_first_access() {
+    let mut storage = storage();
+    let mut recording = RecordingStorage::new(&mut storage);
+    recording.storage::<Balances>().insert(&1, &11).unwrap();
+    recording.storage::<Balances>().get(&1).unwrap();
+    recording.storage::<Balances>().insert(&1, &12).unwrap();
+    recording.storage::<Balances>().get(&3).unwrap();
+    recording.storage::<Balances>().remove(&3).unwrap();
+    assert!(!recording.storage::<Balances>().contains_key(&3).unwrap());
+
+    // The read of the own write of 1 isn't recorded.
+    let reads: Vec<_> = recording.reads::<Balances>().into_iter().collect();
+    assert_eq!(reads, [(3, Some(30))]);
+    let previous: Vec<_> = recording.previous::<Balances>().into_iter().collect();
+    assert_eq!(previous, [(1, Some(10)), (3, Some(30))]);
+    let writes: Vec<_> = recording.writes::<Balances>().into_iter().collect();
+    assert_eq!(writes, [(1, Some(12)), (3, None)]);
+}
+
+#[test]
+fn tables_are_recorded_separately_until_cleared() {
+    let mut storage = storage();
+    let mut recording = RecordingStorage::new(&mut storage);
+    recording.storage::<Balances>().get(&4).unwrap();
+    recording.storage::<Nonces>().insert(&4, &1).unwrap();
+
+    assert_eq!(recording.reads::<Balances>().len(), 1);
+    assert_eq!(recording.writes::<Balances>().len(), 0);
+    assert!(recording.reads::<Nonces>().is_empty());
+    assert_eq!(
+        recording
+            .previous::<Nonces>()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        [(4, None)]
+    );
+
+    recording.clear();
+    assert!(recording.reads::<Balances>().is_empty());
+    assert!(recording.previous::<Nonces>().is_empty());
+    assert!(recording.writes::<Nonces>().is_empty());
+    recording.into_inner();
+    assert_eq!(
+        storage
+            .storage::<Nonces>()
+            .get(&4)
+            .unwrap()
+            .map(|value| value.into_owned()),
+        Some(1)
+    );
+}
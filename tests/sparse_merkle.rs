@@ -0,0 +1,232 @@
+mod common;
+
+use common::{path, reference_root, state_storage, ContractsState, Rng, StateNodes, StateRoots};
+use fuel_storage::{
+    codec::{Array, BigEndian, Compact},
+    merkle::{verify, MerkleError, MerkleHasher, Sha256, SparseMerkleTable, SparseNode, ValueHash},
+    Mappable, MerkleRoot, StorageAsMut, StorageError, Table, TableWithCodec,
+};
+use std::collections::BTreeMap;
+
+/// The copy of the contract state with its own roots, sharing the nodes of the state.
+pub struct MirroredState;
+
+impl Mappable for MirroredState {
+    type Key = (u8, u32);
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+impl Table for MirroredState {
+    const COLUMN: u32 = 3;
+    const NAME: &'static str = "MirroredState";
+}
+
+impl TableWithCodec for MirroredState {
+    type KeyCodec = Compact;
+    type ValueCodec = BigEndian;
+}
+
+impl SparseMerkleTable for MirroredState {
+    type Kind = ValueHash;
+    type Nodes = StateNodes;
+    type Roots = MirroredRoots;
+
+    fn tree_key(key: &Self::Key) -> u8 {
+        key.0
+    }
+}
+
+pub struct MirroredRoots;
+
+impl Mappable for MirroredRoots {
+    type Key = u8;
+    type SetValue = MerkleRoot;
+    type GetValue = MerkleRoot;
+}
+
+impl Table for MirroredRoots {
+    const COLUMN: u32 = 4;
+    const NAME: &'static str = "MirroredRoots";
+}
+
+impl TableWithCodec for MirroredRoots {
+    type KeyCodec = BigEndian;
+    type ValueCodec = Array;
+}
+
+fn hex(string: &str) -> Vec<u8> {
+    (0..string.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&string[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn sha256_matches_the_reference_vectors() {
+    assert_eq!(
+        Sha256::hash(b"").to_vec(),
+        hex("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+    );
+    assert_eq!(
+        Sha256::hash(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq").to_vec(),
+        hex("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1")
+    );
+    let mut hasher = Sha256::default();
+    for _ in 0..1000 {
+        hasher.update(&[b'a'; 1000]);
+    }
+    assert_eq!(
+        hasher.finalize().to_vec(),
+        hex("cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0")
+    );
+}
+
+#[test]
+fn roots_match_trees_built_from_scratch() {
+    let mut storage = state_storage();
+    let mut model = BTreeMap::new();
+    let mut rng = Rng(12345);
+    for step in 0..3000 {
+        let random = rng.next();
+        let key = ((random % 3) as u8, ((random >> 8) % 40) as u32);
+        if (random >> 20).is_multiple_of(3) {
+            assert_eq!(
+                storage.storage::<ContractsState>().remove(&key).unwrap(),
+                model.remove(&key)
+            );
+        } else {
+            let value = random >> 32;
+            assert_eq!(
+                storage
+                    .storage::<ContractsState>()
+                    .insert(&key, &value)
+                    .unwrap(),
+                model.insert(key, value)
+            );
+        }
+
+        if step % 100 == 0 {
+            for tree in 0..3 {
+                let mut leaves: Vec<_> = model
+                    .iter()
+                    .filter(|(key, _)| key.0 == tree)
+                    .map(|(key, value)| (path(key), Sha256::hash(&value.to_be_bytes())))
+                    .collect();
+                leaves.sort();
+                assert_eq!(
+                    storage.storage::<ContractsState>().root(&tree).unwrap(),
+                    reference_root(&leaves, 0),
+                    "step {step}, tree {tree}"
+                );
+            }
+        }
+    }
+
+    for key in model.keys() {
+        storage.storage::<ContractsState>().remove(key).unwrap();
+    }
+    for tree in 0..3 {
+        assert_eq!(
+            storage.storage::<ContractsState>().root(&tree).unwrap(),
+            MerkleRoot::default()
+        );
+    }
+    assert!(storage
+        .inner()
+        .inner()
+        .column(2)
+        .is_none_or(|roots| roots.is_empty()));
+}
+
+#[test]
+fn branches_below_the_paths_are_reported() {
+    let key = (0, 1);
+    let path = path(&key);
+    let mut storage = state_storage();
+    let nodes = storage.inner_mut();
+
+    // The chain of branches along the path continues one level below its last bit.
+    let empty = MerkleRoot::default();
+    let mut node = SparseNode::Branch {
+        left: empty,
+        right: empty,
+    };
+    for depth in (0..256).rev() {
+        let digest = node.digest::<Sha256>().unwrap();
+        nodes
+            .storage::<StateNodes>()
+            .insert(&digest, &node)
+            .unwrap();
+        let (left, right) = if path[depth / 8] >> (7 - depth % 8) & 1 == 1 {
+            (empty, digest)
+        } else {
+            (digest, empty)
+        };
+        node = SparseNode::Branch { left, right };
+    }
+    let root = node.digest::<Sha256>().unwrap();
+    nodes.storage::<StateNodes>().insert(&root, &node).unwrap();
+    nodes.storage::<StateRoots>().insert(&0, &root).unwrap();
+
+    let too_deep = StorageError::Merkle(MerkleError::TooDeep);
+    assert_eq!(
+        storage.storage::<ContractsState>().insert(&key, &1),
+        Err(too_deep.clone())
+    );
+    assert_eq!(
+        storage
+            .storage::<ContractsState>()
+            .prove(&0, &key)
+            .map(|_| ()),
+        Err(too_deep)
+    );
+}
+
+#[test]
+fn tables_sharing_the_nodes_keep_their_trees() {
+    let mut storage = state_storage();
+    // The same entries in both tables make the same trees out of the same nodes.
+    for slot in 0..4 {
+        storage
+            .storage::<ContractsState>()
+            .insert(&(0, slot), &(slot as u64))
+            .unwrap();
+        storage
+            .storage::<MirroredState>()
+            .insert(&(0, slot), &(slot as u64))
+            .unwrap();
+    }
+    let root = storage.storage::<MirroredState>().root(&0).unwrap();
+    assert_eq!(storage.storage::<ContractsState>().root(&0), Ok(root));
+
+    storage.storage::<ContractsState>().remove(&(0, 1)).unwrap();
+    storage
+        .storage::<ContractsState>()
+        .insert(&(0, 2), &20)
+        .unwrap();
+
+    // The updates of one table don't take the nodes away from the other one.
+    let proof = storage
+        .storage::<MirroredState>()
+        .prove(&0, &(0, 1))
+        .unwrap();
+    assert!(verify::<MirroredState, Sha256>(
+        &root,
+        &(0, 1),
+        Some(&1),
+        &proof
+    ));
+    storage
+        .storage::<MirroredState>()
+        .insert(&(0, 4), &4)
+        .unwrap();
+    let mut leaves: Vec<_> = (0..5u32)
+        .map(|slot| (path(&(0, slot)), Sha256::hash(&(slot as u64).to_be_bytes())))
+        .collect();
+    leaves.sort();
+    assert_eq!(
+        storage.storage::<MirroredState>().root(&0),
+        Ok(reference_root(&leaves, 0))
+    );
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::{state_storage, ContractsState};
+use fuel_storage::{
+    codec::{Compact, Decode, Encode},
+    merkle::{verify, Sha256, SparseMerkleProof},
+    StorageAsMut,
+};
+
+#[test]
+fn proves_present_and_absent_keys() {
+    let mut storage = state_storage();
+    for slot in (0..200).filter(|slot| slot % 3 != 0) {
+        storage
+            .storage::<ContractsState>()
+            .insert(&(1, slot), &(slot as u64))
+            .unwrap();
+    }
+    let root = storage.storage::<ContractsState>().root(&1).unwrap();
+
+    for slot in 0..220 {
+        let key = (1, slot);
+        let proof = storage.storage::<ContractsState>().prove(&1, &key).unwrap();
+        if slot < 200 && slot % 3 != 0 {
+            let value = slot as u64;
+            assert!(verify::<ContractsState, Sha256>(
+                &root,
+                &key,
+                Some(&value),
+                &proof
+            ));
+            assert!(!verify::<ContractsState, Sha256>(
+                &root,
+                &key,
+                Some(&(value + 1)),
+                &proof
+            ));
+            assert!(!verify::<ContractsState, Sha256>(&root, &key, None, &proof));
+        } else {
+            assert!(verify::<ContractsState, Sha256>(&root, &key, None, &proof));
+            assert!(!verify::<ContractsState, Sha256>(
+                &root,
+                &key,
+                Some(&0),
+                &proof
+            ));
+        }
+        assert!(!verify::<ContractsState, Sha256>(
+            &[1; 32], &key, None, &proof
+        ));
+    }
+}
+
+#[test]
+fn proves_absence_next_to_a_single_leaf() {
+    let mut storage = state_storage();
+    storage
+        .storage::<ContractsState>()
+        .insert(&(2, 5), &5)
+        .unwrap();
+    let root = storage.storage::<ContractsState>().root(&2).unwrap();
+
+    let proof = storage
+        .storage::<ContractsState>()
+        .prove(&2, &(2, 6))
+        .unwrap();
+    assert!(verify::<ContractsState, Sha256>(
+        &root,
+        &(2, 6),
+        None,
+        &proof
+    ));
+    let proof = storage
+        .storage::<ContractsState>()
+        .prove(&3, &(3, 6))
+        .unwrap();
+    assert!(verify::<ContractsState, Sha256>(
+        &Default::default(),
+        &(3, 6),
+        None,
+        &proof
+    ));
+}
+
+#[test]
+fn proofs_round_trip_through_the_codec() {
+    let mut storage = state_storage();
+    for slot in 0..20 {
+        storage
+            .storage::<ContractsState>()
+            .insert(&(0, slot), &1)
+            .unwrap();
+    }
+    let proof = storage
+        .storage::<ContractsState>()
+        .prove(&0, &(0, 7))
+        .unwrap();
+    let bytes = <Compact as Encode<SparseMerkleProof>>::encode(&proof).into_owned();
+    let decoded: SparseMerkleProof = Compact::decode(&bytes).unwrap();
+    assert_eq!(decoded, proof);
+}
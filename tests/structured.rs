@@ -0,0 +1,92 @@
+use core::ops::Bound;
+use fuel_storage::{
+    codec::{BigEndian, Compact, DecodeError},
+    IterDirection, KeyValueStore, Mappable, MemoryKeyValueStore, StorageAsMut, StorageError,
+    StorageTransaction, StructuredStorage, Table, TableWithCodec,
+};
+
+pub struct Balances;
+
+impl Mappable for Balances {
+    type Key = u32;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+impl Table for Balances {
+    const COLUMN: u32 = 3;
+    const NAME: &'static str = "Balances";
+}
+
+impl TableWithCodec for Balances {
+    type KeyCodec = BigEndian;
+    type ValueCodec = Compact;
+}
+
+fn storage() -> StructuredStorage<MemoryKeyValueStore> {
+    let mut storage = StructuredStorage::new(MemoryKeyValueStore::new());
+    for key in 0..300 {
+        storage
+            .storage::<Balances>()
+            .insert(&key, &(key as u64 * 1000))
+            .unwrap();
+    }
+    storage
+}
+
+#[test]
+fn stores_encoded_entries_under_the_column() {
+    let mut storage = storage();
+    assert_eq!(storage.storage::<Balances>().insert(&1, &7), Ok(Some(1000)));
+    assert_eq!(
+        storage.inner().get(Balances::COLUMN, &1u32.to_be_bytes()),
+        Ok(Some(vec![7]))
+    );
+    assert_eq!(storage.storage::<Balances>().remove(&1), Ok(Some(7)));
+    assert!(!storage
+        .inner()
+        .exists(Balances::COLUMN, &1u32.to_be_bytes())
+        .unwrap());
+}
+
+#[test]
+fn iterates_in_the_order_of_big_endian_keys() {
+    let mut storage = storage();
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction.storage::<Balances>().remove(&5).unwrap();
+    transaction.storage::<Balances>().insert(&1000, &1).unwrap();
+
+    let keys: Vec<_> = transaction
+        .storage::<Balances>()
+        .iter_range(
+            Bound::Included(&3),
+            Bound::Included(&6),
+            IterDirection::Forward,
+        )
+        .map(|item| item.unwrap().0)
+        .collect();
+    assert_eq!(keys, vec![3, 4, 6]);
+
+    let last: Vec<_> = transaction
+        .storage::<Balances>()
+        .iter_all(IterDirection::Reverse)
+        .take(2)
+        .map(|item| item.unwrap())
+        .collect();
+    assert_eq!(last, vec![(1000, 1), (299, 299000)]);
+}
+
+#[test]
+fn reports_corrupted_values() {
+    let mut storage = storage();
+    storage
+        .inner_mut()
+        .put(Balances::COLUMN, &1u32.to_be_bytes(), &[0xff])
+        .unwrap();
+
+    let value = storage.storage::<Balances>().get(&1);
+    assert_eq!(
+        value.map(|value| value.map(|value| value.into_owned())),
+        Err(StorageError::Codec(DecodeError::UnexpectedEnd))
+    );
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use common::Rng;
+use fuel_storage::{
+    codec::{Array, BigEndian, Compact},
+    merkle::{
+        verify, MerkleError, Sha256, SparseMerkleStorage, SparseMerkleTable, SparseNode, Sum,
+    },
+    Mappable, MemoryKeyValueStore, MerkleRoot, StorageAsMut, StorageError, StructuredStorage,
+    Table, TableWithCodec,
+};
+use std::collections::BTreeMap;
+
+/// The balances of assets, keyed by the account and the asset, one sum tree per account.
+pub struct Balances;
+
+impl Mappable for Balances {
+    type Key = (u8, u32);
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+impl Table for Balances {
+    const COLUMN: u32 = 0;
+    const NAME: &'static str = "Balances";
+}
+
+impl TableWithCodec for Balances {
+    type KeyCodec = Compact;
+    type ValueCodec = BigEndian;
+}
+
+impl SparseMerkleTable for Balances {
+    type Kind = Sum;
+    type Nodes = BalancesNodes;
+    type Roots = BalancesRoots;
+
+    fn tree_key(key: &Self::Key) -> u8 {
+        key.0
+    }
+}
+
+pub struct BalancesNodes;
+
+impl Mappable for BalancesNodes {
+    type Key = MerkleRoot;
+    type SetValue = SparseNode<(MerkleRoot, u64)>;
+    type GetValue = SparseNode<(MerkleRoot, u64)>;
+}
+
+impl Table for BalancesNodes {
+    const COLUMN: u32 = 1;
+    const NAME: &'static str = "BalancesNodes";
+}
+
+impl TableWithCodec for BalancesNodes {
+    type KeyCodec = Array;
+    type ValueCodec = Compact;
+}
+
+pub struct BalancesRoots;
+
+impl Mappable for BalancesRoots {
+    type Key = u8;
+    type SetValue = (MerkleRoot, u64);
+    type GetValue = (MerkleRoot, u64);
+}
+
+impl Table for BalancesRoots {
+    const COLUMN: u32 = 2;
+    const NAME: &'static str = "BalancesRoots";
+}
+
+impl TableWithCodec for BalancesRoots {
+    type KeyCodec = BigEndian;
+    type ValueCodec = Compact;
+}
+
+type BalancesStorage = SparseMerkleStorage<StructuredStorage<MemoryKeyValueStore>>;
+
+fn balances_storage() -> BalancesStorage {
+    SparseMerkleStorage::new(StructuredStorage::new(MemoryKeyValueStore::new()))
+}
+
+#[test]
+fn roots_commit_to_the_totals_of_random_updates() {
+    let mut storage = balances_storage();
+    let mut model = BTreeMap::new();
+    let mut rng = Rng(7);
+    for _ in 0..300 {
+        let random = rng.next();
+        let key = (0, (random >> 40) as u32 % 40);
+        if random.is_multiple_of(3) {
+            storage.storage::<Balances>().remove(&key).unwrap();
+            model.remove(&key);
+        } else {
+            let value = (random >> 20) % 1000;
+            storage.storage::<Balances>().insert(&key, &value).unwrap();
+            model.insert(key, value);
+        }
+
+        let (root, total) = storage.storage::<Balances>().root_and_sum(&0).unwrap();
+        assert_eq!(total, model.values().sum::<u64>());
+        for asset in 0..40 {
+            let key = (0, asset);
+            let proof = storage.storage::<Balances>().prove(&0, &key).unwrap();
+            assert!(verify::<Balances, Sha256>(
+                &(root, total),
+                &key,
+                model.get(&key),
+                &proof
+            ));
+        }
+    }
+
+    for key in model.keys() {
+        storage.storage::<Balances>().remove(key).unwrap();
+    }
+    assert_eq!(
+        storage.storage::<Balances>().root_and_sum(&0),
+        Ok(([0; 32], 0))
+    );
+}
+
+#[test]
+fn overflowing_updates_leave_the_storage_unchanged() {
+    let mut storage = balances_storage();
+    for asset in 0..10 {
+        storage
+            .storage::<Balances>()
+            .insert(&(1, asset), &(u64::MAX / 20))
+            .unwrap();
+    }
+    let before = storage.inner().inner().clone();
+
+    for key in [(1, 3), (1, 100)] {
+        let overflow = storage.storage::<Balances>().insert(&key, &u64::MAX);
+        assert_eq!(
+            overflow,
+            Err(StorageError::Merkle(MerkleError::SumOverflow))
+        );
+    }
+    assert_eq!(storage.inner().inner(), &before);
+
+    // The other trees are summed separately.
+    storage
+        .storage::<Balances>()
+        .insert(&(2, 0), &u64::MAX)
+        .unwrap();
+    assert_eq!(
+        storage.storage::<Balances>().root_and_sum(&2).unwrap().1,
+        u64::MAX
+    );
+}
@@ -0,0 +1,41 @@
+use fuel_storage::{assert_unique_columns, find_duplicate_column, Mappable, Table, TableInfo};
+
+macro_rules! table {
+    ($name:ident, $column:expr) => {
+        pub struct $name;
+
+        impl Mappable for $name {
+            type Key = u32;
+            type SetValue = u64;
+            type GetValue = u64;
+        }
+
+        impl Table for $name {
+            const COLUMN: u32 = $column;
+            const NAME: &'static str = stringify!($name);
+        }
+    };
+}
+
+table!(Coins, 0);
+table!(Balances, 1);
+table!(Messages, 2);
+table!(Receipts, 1);
+
+assert_unique_columns!(Coins, Balances, Messages);
+
+#[test]
+fn finds_the_first_duplicate_column() {
+    let tables = [
+        TableInfo::of::<Coins>(),
+        TableInfo::of::<Balances>(),
+        TableInfo::of::<Messages>(),
+        TableInfo::of::<Receipts>(),
+    ];
+    assert_eq!(
+        find_duplicate_column(&tables),
+        Some((TableInfo::of::<Balances>(), TableInfo::of::<Receipts>()))
+    );
+    assert_eq!(find_duplicate_column(&tables[..3]), None);
+    assert_eq!(TableInfo::of::<Receipts>().name, "Receipts");
+}
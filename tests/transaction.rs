@@ -0,0 +1,182 @@
+use fuel_storage::{
+    codec::{BigEndian, Raw},
+    Mappable, MemoryKeyValueStore, MemoryStorage, StorageAsMut, StorageError, StorageTransaction,
+    StructuredStorage, Table, TableWithCodec,
+};
+use std::convert::Infallible;
+
+pub struct Balances;
+
+impl Mappable for Balances {
+    type Key = u32;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+pub struct Bytecode;
+
+impl Mappable for Bytecode {
+    type Key = u32;
+    type SetValue = [u8];
+    type GetValue = Vec<u8>;
+}
+
+impl Table for Bytecode {
+    const COLUMN: u32 = 1;
+    const NAME: &'static str = "Bytecode";
+}
+
+impl TableWithCodec for Bytecode {
+    type KeyCodec = BigEndian;
+    type ValueCodec = Raw;
+}
+
+fn balances(storage: &MemoryStorage) -> Vec<(u32, u64)> {
+    storage
+        .table::<Balances>()
+        .map(|table| table.iter().map(|(key, value)| (*key, *value)).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn commit_applies_the_overlay() {
+    let mut storage = MemoryStorage::new();
+    storage.storage::<Balances>().insert(&1, &10).unwrap();
+    storage.storage::<Balances>().insert(&2, &20).unwrap();
+
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction.storage::<Balances>().insert(&1, &11).unwrap();
+    transaction.storage::<Balances>().remove(&2).unwrap();
+    transaction.storage::<Balances>().insert(&3, &30).unwrap();
+    assert_eq!(balances(transaction.inner()), vec![(1, 10), (2, 20)]);
+    assert_eq!(
+        transaction
+            .storage::<Balances>()
+            .get(&1)
+            .unwrap()
+            .unwrap()
+            .into_owned(),
+        11
+    );
+    assert!(!transaction.storage::<Balances>().contains_key(&2).unwrap());
+
+    transaction.commit::<Infallible>().unwrap();
+    assert_eq!(balances(&storage), vec![(1, 11), (3, 30)]);
+}
+
+#[test]
+fn rollback_and_drop_discard_the_overlay() {
+    let mut storage = MemoryStorage::new();
+    storage.storage::<Balances>().insert(&1, &10).unwrap();
+
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction.storage::<Balances>().remove(&1).unwrap();
+    transaction.rollback();
+
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction.storage::<Balances>().insert(&2, &20).unwrap();
+    drop(transaction);
+
+    assert_eq!(balances(&storage), vec![(1, 10)]);
+}
+
+#[test]
+fn nested_transactions_commit_into_the_enclosing_one() {
+    let mut storage = MemoryStorage::new();
+    let mut outer = StorageTransaction::new(&mut storage);
+    outer.storage::<Balances>().insert(&1, &10).unwrap();
+
+    let mut inner = StorageTransaction::new(&mut outer);
+    assert_eq!(inner.storage::<Balances>().insert(&1, &20), Ok(Some(10)));
+    inner.commit::<Infallible>().unwrap();
+
+    let mut inner = StorageTransaction::new(&mut outer);
+    inner.storage::<Balances>().insert(&2, &20).unwrap();
+    inner.rollback();
+
+    outer.commit::<Infallible>().unwrap();
+    assert_eq!(balances(&storage), vec![(1, 20)]);
+}
+
+#[test]
+fn reports_the_error_of_the_underlying_storage() {
+    let mut storage = StructuredStorage::new(MemoryKeyValueStore::new());
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction
+        .storage::<Bytecode>()
+        .insert(&1, &[1, 2, 3])
+        .unwrap();
+
+    let committed: Result<_, StorageError> = transaction.commit();
+    committed.unwrap();
+    assert_eq!(
+        storage
+            .storage::<Bytecode>()
+            .get(&1)
+            .unwrap()
+            .unwrap()
+            .into_owned(),
+        vec![1, 2, 3]
+    );
+}
+
+#[test]
+#[should_panic(expected = "differs from the error of the commit")]
+fn commit_rejects_another_error_before_applying() {
+    let mut storage = MemoryStorage::new();
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction.storage::<Balances>().insert(&1, &10).unwrap();
+
+    let _ = transaction.commit::<StorageError>();
+}
+
+#[test]
+fn savepoints_undo_only_their_own_changes() {
+    let mut storage = MemoryStorage::new();
+    let mut transaction = StorageTransaction::new(&mut storage);
+    transaction.storage::<Balances>().insert(&1, &10).unwrap();
+    let outer = transaction.savepoint();
+    transaction.storage::<Balances>().insert(&2, &20).unwrap();
+    let inner = transaction.savepoint();
+    transaction.storage::<Balances>().insert(&3, &30).unwrap();
+    transaction.storage::<Balances>().remove(&1).unwrap();
+    transaction.release(inner);
+    assert!(!transaction.storage::<Balances>().contains_key(&1).unwrap());
+
+    let reverted = transaction.savepoint();
+    transaction.storage::<Balances>().insert(&4, &40).unwrap();
+    transaction.storage::<Balances>().insert(&2, &21).unwrap();
+    transaction.rollback_to(reverted);
+    transaction.release(outer);
+
+    transaction.commit::<Infallible>().unwrap();
+    assert_eq!(balances(&storage), vec![(2, 20), (3, 30)]);
+}
+
+#[test]
+fn rolling_back_a_savepoint_discards_the_newer_ones() {
+    let mut storage = MemoryStorage::new();
+    let mut transaction = StorageTransaction::new(&mut storage);
+    let outer = transaction.savepoint();
+    transaction.storage::<Balances>().insert(&1, &10).unwrap();
+    let _inner = transaction.savepoint();
+    transaction.storage::<Balances>().insert(&2, &20).unwrap();
+    transaction.rollback_to(outer);
+
+    assert!(transaction.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "The savepoint was rolled back")]
+fn released_savepoints_do_not_alias_newer_ones() {
+    let mut storage = MemoryStorage::new();
+    let mut transaction = StorageTransaction::new(&mut storage);
+    let a = transaction.savepoint();
+    let b = transaction.savepoint();
+    transaction.release(a);
+    let _c = transaction.savepoint();
+    let _d = transaction.savepoint();
+    transaction.storage::<Balances>().insert(&1, &10).unwrap();
+
+    transaction.rollback_to(b);
+}
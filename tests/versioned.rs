@@ -0,0 +1,154 @@
+use fuel_storage::{
+    kv_store::KeyValueStore, Changes, MemoryKeyValueStore, PruningPolicy, StorageError,
+    VersionError, VersionedStorage,
+};
+
+type Versioned = VersionedStorage<MemoryKeyValueStore>;
+
+fn history() -> Versioned {
+    let mut storage = VersionedStorage::new(MemoryKeyValueStore::new());
+    let mut block = Changes::new();
+    block.put(0, b"a", b"1");
+    block.put(0, b"ab", b"x");
+    storage.commit(10, &block).unwrap();
+    let mut block = Changes::new();
+    block.put(0, b"a", b"2");
+    block.put(1, b"a", b"y");
+    storage.commit(12, &block).unwrap();
+    let mut block = Changes::new();
+    block.delete(0, b"ab");
+    block.delete(0, b"a");
+    storage.commit(15, &block).unwrap();
+    storage
+}
+
+#[test]
+fn serves_the_state_at_every_retained_height() {
+    let storage = history();
+    assert_eq!(storage.heights(), Ok(Some((10, 15))));
+
+    let at = |height, column, key: &[u8]| storage.storage_at(height).unwrap().get(column, key);
+    assert_eq!(at(10, 0, b"a"), Ok(Some(b"1".to_vec())));
+    assert_eq!(at(11, 0, b"a"), Ok(Some(b"1".to_vec())));
+    assert_eq!(at(12, 0, b"a"), Ok(Some(b"2".to_vec())));
+    assert_eq!(at(14, 0, b"ab"), Ok(Some(b"x".to_vec())));
+    assert_eq!(at(15, 0, b"ab"), Ok(None));
+    assert_eq!(at(10, 1, b"a"), Ok(None));
+    assert_eq!(at(15, 1, b"a"), Ok(Some(b"y".to_vec())));
+    assert_eq!(storage.inner().get(0, b"a"), Ok(None));
+
+    for height in [0, 9, 16] {
+        assert_eq!(
+            storage.storage_at(height).err(),
+            Some(StorageError::Version(VersionError::Unretained(height)))
+        );
+    }
+}
+
+#[test]
+fn commits_return_the_inverse_changes() {
+    let mut storage = history();
+    let mut block = Changes::new();
+    block.put(1, b"a", b"z");
+    block.put(1, b"b", b"z");
+    let inverse = storage.commit(20, &block).unwrap();
+
+    let mut expected = Changes::new();
+    expected.put(1, b"a", b"y");
+    expected.delete(1, b"b");
+    assert_eq!(inverse, expected);
+}
+
+#[test]
+fn rejects_old_heights_and_reserved_columns() {
+    let mut storage = history();
+    assert_eq!(
+        storage.commit(15, &Changes::new()),
+        Err(StorageError::Version(VersionError::NonIncreasingHeight {
+            height: 15,
+            latest: 15
+        }))
+    );
+
+    for column in [
+        Versioned::HISTORY_COLUMN,
+        Versioned::VERSIONS_COLUMN,
+        Versioned::CHECKPOINTS_COLUMN,
+    ] {
+        let mut block = Changes::new();
+        block.put(column, b"k", b"v");
+        assert_eq!(
+            storage.commit(16, &block),
+            Err(StorageError::Version(VersionError::ReservedColumn(column)))
+        );
+    }
+    assert_eq!(storage.heights(), Ok(Some((10, 15))));
+}
+
+#[test]
+fn checkpoints_are_stored_one_per_key() {
+    let mut storage =
+        VersionedStorage::with_policy(MemoryKeyValueStore::new(), PruningPolicy::Checkpoints(4));
+    for height in 1..=10u64 {
+        let mut block = Changes::new();
+        block.put(0, b"height", &height.to_be_bytes());
+        storage.commit(height, &block).unwrap();
+    }
+
+    let checkpoints: Vec<_> = storage
+        .inner()
+        .column(Versioned::CHECKPOINTS_COLUMN)
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    assert_eq!(
+        checkpoints,
+        [4u64.to_be_bytes().to_vec(), 8u64.to_be_bytes().to_vec()]
+    );
+    assert_eq!(storage.heights(), Ok(Some((4, 10))));
+
+    // Wrapping the store with another policy drops the checkpoints it doesn't keep.
+    let mut storage =
+        VersionedStorage::with_policy(storage.into_inner(), PruningPolicy::Checkpoints(8));
+    storage.commit(11, &Changes::new()).unwrap();
+    let retained: Vec<_> = (1..=11)
+        .filter(|height| storage.is_retained(*height).unwrap())
+        .collect();
+    assert_eq!(retained, [8, 11]);
+    let view = storage.storage_at(8).unwrap();
+    assert_eq!(
+        view.get(0, b"height"),
+        Ok(Some(8u64.to_be_bytes().to_vec()))
+    );
+}
+
+#[test]
+fn failed_pruning_discards_the_commit() {
+    let mut storage =
+        VersionedStorage::with_policy(MemoryKeyValueStore::new(), PruningPolicy::KeepLast(1));
+    for height in 1..=2u64 {
+        let mut block = Changes::new();
+        block.put(0, b"height", &height.to_be_bytes());
+        storage.commit(height, &block).unwrap();
+    }
+
+    // The corrupted history makes the pruning after the next commit fail.
+    let mut store = storage.into_inner();
+    store
+        .put(Versioned::VERSIONS_COLUMN, &2u64.to_be_bytes(), &[0xff])
+        .unwrap();
+    let mut storage = VersionedStorage::with_policy(store, PruningPolicy::KeepLast(1));
+    let mut block = Changes::new();
+    block.put(0, b"height", &3u64.to_be_bytes());
+    assert!(matches!(
+        storage.commit(3, &block),
+        Err(StorageError::Codec(_))
+    ));
+
+    assert_eq!(storage.heights(), Ok(Some((2, 2))));
+    assert_eq!(
+        storage.inner().get(0, b"height"),
+        Ok(Some(2u64.to_be_bytes().to_vec()))
+    );
+}
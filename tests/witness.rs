@@ -0,0 +1,83 @@
+mod common;
+
+use common::{state_storage, ContractsState};
+use fuel_storage::{
+    codec::DecodeError,
+    merkle::{ProvenStorage, Sha256, StateWitness},
+    StorageAsMut,
+};
+
+#[test]
+fn witnesses_of_several_trees_replay_the_updates() {
+    let mut storage = state_storage();
+    for contract in 0..3 {
+        for slot in 0..30 {
+            storage
+                .storage::<ContractsState>()
+                .insert(&(contract, slot), &(slot as u64 * 7))
+                .unwrap();
+        }
+    }
+
+    let keys = [(2, 5), (0, 1), (0, 100), (2, 5), (1, 29)];
+    let witness =
+        StateWitness::<ContractsState>::generate::<_, Sha256>(&mut storage, &keys).unwrap();
+    assert_eq!(witness.trees.len(), 3);
+    assert_eq!(
+        witness.trees[0].entries,
+        [((0, 1), Some(7)), ((0, 100), None)]
+    );
+    assert_eq!(witness.trees[2].entries, [((2, 5), Some(35))]);
+
+    let bytes = witness.encode();
+    let witness = StateWitness::<ContractsState>::decode(&bytes).unwrap();
+    assert_eq!(witness.encode(), bytes);
+
+    for (contract, tree) in witness.trees.iter().enumerate() {
+        let contract = contract as u8;
+        let mut proven = ProvenStorage::<ContractsState>::from_witness(tree).unwrap();
+        let root = storage.storage::<ContractsState>().root(&contract).unwrap();
+        assert_eq!(proven.root(), root);
+        for (key, value) in &tree.entries {
+            let proven_value = proven.storage::<ContractsState>().get(key).unwrap();
+            assert_eq!(proven_value.map(|value| value.into_owned()), *value);
+        }
+
+        let key = tree.entries[0].0;
+        proven.storage::<ContractsState>().insert(&key, &1).unwrap();
+        storage
+            .storage::<ContractsState>()
+            .insert(&key, &1)
+            .unwrap();
+        let root = storage.storage::<ContractsState>().root(&contract).unwrap();
+        assert_eq!(proven.root(), root);
+    }
+}
+
+#[test]
+fn rejects_malformed_encodings() {
+    let mut storage = state_storage();
+    storage
+        .storage::<ContractsState>()
+        .insert(&(0, 1), &1)
+        .unwrap();
+    let witness =
+        StateWitness::<ContractsState>::generate::<_, Sha256>(&mut storage, &[(0, 1)]).unwrap();
+    let bytes = witness.encode();
+
+    let mut unsupported = bytes.clone();
+    unsupported[0] = 9;
+    assert_eq!(
+        StateWitness::<ContractsState>::decode(&unsupported).err(),
+        Some(DecodeError::UnsupportedVersion(9))
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        StateWitness::<ContractsState>::decode(&trailing).err(),
+        Some(DecodeError::TrailingBytes)
+    );
+
+    assert!(StateWitness::<ContractsState>::decode(&bytes[..bytes.len() - 1]).is_err());
+}
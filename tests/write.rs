@@ -0,0 +1,97 @@
+use fuel_storage::{
+    codec::{BigEndian, Compact},
+    KeyValueStore, Mappable, MemoryKeyValueStore, MemoryStorage, RecordingStorage, StorageAsMut,
+    StorageError, StorageTransaction, StorageWrite, StructuredStorage, Table, TableWithCodec,
+};
+use std::{cell::Cell, convert::Infallible};
+
+pub struct Balances;
+
+impl Mappable for Balances {
+    type Key = u32;
+    type SetValue = u64;
+    type GetValue = u64;
+}
+
+impl Table for Balances {
+    const COLUMN: u32 = 0;
+    const NAME: &'static str = "Balances";
+}
+
+impl TableWithCodec for Balances {
+    type KeyCodec = BigEndian;
+    type ValueCodec = Compact;
+}
+
+/// The store counting the values read from it.
+#[derive(Default)]
+struct CountingStore {
+    store: MemoryKeyValueStore,
+    reads: Cell<usize>,
+}
+
+impl KeyValueStore for CountingStore {
+    type Error = StorageError;
+
+    fn get(&self, column: u32, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.reads.set(self.reads.get() + 1);
+        self.store.get(column, key)
+    }
+
+    fn put(&mut self, column: u32, key: &[u8], value: &[u8]) -> Result<(), Self::Error> {
+        self.store.put(column, key, value)
+    }
+
+    fn delete(&mut self, column: u32, key: &[u8]) -> Result<(), Self::Error> {
+        self.store.delete(column, key)
+    }
+}
+
+#[test]
+fn writes_without_returning_previous_values() {
+    let mut storage = MemoryStorage::new();
+    storage.storage::<Balances>().write(&1, &10).unwrap();
+    storage.storage::<Balances>().write(&1, &11).unwrap();
+    assert_eq!(storage.storage::<Balances>().replace(&1, &12), Ok(Some(11)));
+    assert_eq!(storage.storage::<Balances>().take(&1), Ok(Some(12)));
+    storage.storage::<Balances>().delete(&1).unwrap();
+    assert!(!storage.storage::<Balances>().contains_key(&1).unwrap());
+}
+
+#[test]
+fn wrappers_accept_writes() {
+    let mut storage = MemoryStorage::new();
+    let mut recording = RecordingStorage::new(&mut storage);
+    StorageWrite::<Balances>::write(&mut recording, &1, &10).unwrap();
+    StorageWrite::<Balances>::delete(&mut recording, &2).unwrap();
+
+    let writes = recording.writes::<Balances>();
+    assert_eq!(
+        writes.into_iter().collect::<Vec<_>>(),
+        [(1, Some(10)), (2, None)]
+    );
+    assert_eq!(storage.table::<Balances>().unwrap().get(&1), Some(&10));
+}
+
+#[test]
+fn backends_write_without_reading() {
+    let mut storage = MemoryStorage::new();
+    storage.storage::<Balances>().insert(&1, &10).unwrap();
+    let mut transaction = StorageTransaction::new(RecordingStorage::new(&mut storage));
+    transaction.storage::<Balances>().write(&1, &11).unwrap();
+    transaction.storage::<Balances>().write(&2, &20).unwrap();
+    transaction.storage::<Balances>().delete(&3).unwrap();
+    assert!(transaction.inner().reads::<Balances>().is_empty());
+    assert_eq!(transaction.storage::<Balances>().take(&2), Ok(Some(20)));
+    let committed: Result<_, Infallible> = transaction.commit();
+    committed.unwrap();
+    assert_eq!(storage.table::<Balances>().unwrap().get(&1), Some(&11));
+
+    let mut storage = StructuredStorage::new(CountingStore::default());
+    storage.storage::<Balances>().write(&1, &10).unwrap();
+    storage.storage::<Balances>().write(&1, &11).unwrap();
+    storage.storage::<Balances>().delete(&2).unwrap();
+    assert_eq!(storage.inner().reads.get(), 0);
+    assert_eq!(storage.storage::<Balances>().take(&1), Ok(Some(11)));
+    assert_eq!(storage.inner().reads.get(), 1);
+}